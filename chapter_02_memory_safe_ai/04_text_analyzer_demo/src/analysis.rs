@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::emotion::EmotionScores;
+use crate::language::LanguageDetection;
+use crate::redact::PiiKind;
+
+// Core data structures - showing Rust's type safety
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextAnalysis {
+    pub sentiment: SentimentScore,
+    pub key_topics: Vec<Topic>,
+    pub confidence: f64,
+    #[serde(rename = "processing_time_ms", with = "duration_ms")]
+    pub processing_time: Duration,
+    /// Identifies this call in the `analyze_text` tracing span; nil in
+    /// records written before request IDs existed.
+    #[serde(default)]
+    pub request_id: Uuid,
+    /// The language the text was scored as.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<LanguageDetection>,
+    /// Backend calls made to produce this result, retries included; 0 when it
+    /// was served from the cache.
+    #[serde(default)]
+    pub attempts: u32,
+    /// Personal data replaced with placeholders before the text reached the
+    /// backend, counted by kind.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub redactions: BTreeMap<PiiKind, usize>,
+    /// Emotion shares from the analyzer's emotion lexicon, when it was asked
+    /// for them and the text is in the lexicon's language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emotions: Option<EmotionScores>,
+    /// Per-chunk results when the input was too long and analyzed in chunks;
+    /// the fields above then aggregate them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<ChunkAnalysis>,
+    /// Per-sentence scores, when the analyzer was asked for them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sentences: Vec<SentenceSentiment>,
+}
+
+impl TextAnalysis {
+    /// A backend's result; the fields the analyzer fills in are left empty.
+    pub fn new(sentiment: SentimentScore, key_topics: Vec<Topic>, confidence: f64) -> Self {
+        Self {
+            sentiment,
+            key_topics,
+            confidence,
+            processing_time: Duration::ZERO,
+            request_id: Uuid::nil(),
+            language: None,
+            attempts: 0,
+            redactions: BTreeMap::new(),
+            emotions: None,
+            chunks: Vec::new(),
+            sentences: Vec::new(),
+        }
+    }
+
+    /// A neutral, topic-free result of one backend call, for tests to
+    /// override the fields they care about.
+    #[cfg(test)]
+    pub(crate) fn neutral_for_test() -> Self {
+        let neutral = SentimentScore { positive: 0.0, negative: 0.0, neutral: 1.0 };
+        Self { attempts: 1, ..Self::new(neutral, Vec::new(), 1.0) }
+    }
+}
+
+/// The analysis of one chunk of a long input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkAnalysis {
+    /// Byte offsets of the chunk in the original input.
+    pub start: usize,
+    pub end: usize,
+    /// Its topic mentions are offsets into the whole input too.
+    pub analysis: TextAnalysis,
+}
+
+/// The sentiment of one sentence of the input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentenceSentiment {
+    /// Byte offsets of the sentence in the original, unsanitized input.
+    pub start: usize,
+    pub end: usize,
+    pub sentiment: SentimentScore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentimentScore {
+    pub positive: f64,
+    pub negative: f64,
+    pub neutral: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Topic {
+    pub name: String,
+    pub relevance: f64,
+    pub category: String,
+    /// How the text speaks about this topic, scored around its mentions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sentiment: Option<SentimentScore>,
+    /// Where the topic occurs, as byte offsets in the original, unsanitized
+    /// input, like `sentences`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mentions: Vec<Mention>,
+}
+
+/// A byte range in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mention {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Serializes a `Duration` as fractional milliseconds rather than serde's
+/// default `{ secs, nanos }` struct, so the output schema stays flat.
+pub mod duration_ms {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let millis = f64::deserialize(deserializer)?;
+        if !millis.is_finite() || millis < 0.0 {
+            return Err(de::Error::custom("duration must be a non-negative number of milliseconds"));
+        }
+        Ok(Duration::from_secs_f64(millis / 1000.0))
+    }
+}
@@ -0,0 +1,1076 @@
+use futures::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::analysis::{ChunkAnalysis, Mention, SentenceSentiment, TextAnalysis};
+use crate::backend::{AnalysisBackend, MockBackend};
+use crate::cache::{AnalysisCache, CacheStats};
+use crate::chunking::{self, ChunkingConfig};
+use crate::circuit_breaker::{BreakerState, CircuitBreaker, Permit};
+use crate::emotion::EmotionLexicon;
+use crate::error::AnalysisError;
+use crate::language::{Language, LanguageDetection, LanguageDetector};
+use crate::metrics::Metrics;
+use crate::redact;
+use crate::retry::RetryPolicy;
+use crate::sanitize::{SanitizationPolicy, Sanitized};
+use crate::sentiment::Lexicon;
+use crate::text::{self, OffsetMap};
+
+/// Default limit on a single backend call.
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Main analyzer - production patterns in action
+pub struct TextAnalyzer {
+    circuit_breaker: Mutex<CircuitBreaker>,
+    backend: Box<dyn AnalysisBackend>,
+    backend_identity: String,
+    batch_concurrency: usize,
+    sanitization: SanitizationPolicy,
+    cache: Option<Mutex<AnalysisCache>>,
+    language_detector: LanguageDetector,
+    default_language: Language,
+    chunking: Option<ChunkingConfig>,
+    // Bundled lexicons by language, when a sentence breakdown is wanted
+    sentence_lexicons: Option<BTreeMap<Language, Lexicon>>,
+    emotions: Option<EmotionLexicon>,
+    metrics: Metrics,
+    retry: RetryPolicy,
+    call_timeout: Duration,
+    deadline: Option<Duration>,
+    redact_pii: bool,
+}
+
+impl TextAnalyzer {
+    pub fn new() -> Result<Self, AnalysisError> {
+        Ok(Self::with_backend(Box::new(MockBackend::new())))
+    }
+
+    /// Builds an analyzer whose sanitization and circuit breaker wrap `backend`.
+    pub fn with_backend(backend: Box<dyn AnalysisBackend>) -> Self {
+        Self {
+            circuit_breaker: Mutex::new(CircuitBreaker::new()),
+            backend_identity: backend.identity(),
+            backend,
+            batch_concurrency: 8,
+            sanitization: SanitizationPolicy::default(),
+            cache: None,
+            language_detector: LanguageDetector::new(),
+            default_language: Language::English,
+            chunking: None,
+            sentence_lexicons: None,
+            emotions: None,
+            metrics: Metrics::new(),
+            retry: RetryPolicy::default(),
+            call_timeout: DEFAULT_CALL_TIMEOUT,
+            deadline: None,
+            redact_pii: true,
+        }
+    }
+
+    /// Replaces the default breaker, e.g. to tune its threshold and cooldown.
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Mutex::new(circuit_breaker);
+        self
+    }
+
+    /// Controls how transient backend failures are retried; the breaker only
+    /// sees the outcome of the last attempt.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Abandons a backend call that takes longer than `timeout` with
+    /// `AnalysisError::Timeout`, which is retried like other transient failures.
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+
+    /// Fails an analysis not finished within `deadline` with
+    /// `AnalysisError::DeadlineExceeded`, however many calls and retries it
+    /// involved. `analyze_batch` applies it to the whole batch.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Maximum number of backend calls `analyze_batch` keeps in flight.
+    pub fn with_batch_concurrency(mut self, limit: usize) -> Self {
+        self.batch_concurrency = limit.max(1);
+        self
+    }
+
+    pub fn batch_concurrency(&self) -> usize {
+        self.batch_concurrency
+    }
+
+    /// Controls which characters reach the backend and how input size is measured.
+    pub fn with_sanitization_policy(mut self, policy: SanitizationPolicy) -> Self {
+        self.sanitization = policy;
+        self
+    }
+
+    /// Largest input `sanitize_input` accepts, in the policy's limit unit.
+    pub fn with_max_input_size(mut self, limit: usize) -> Self {
+        self.sanitization.max_input = limit;
+        self
+    }
+
+    /// Language assumed for text too short or ambiguous to identify.
+    pub fn with_default_language(mut self, language: Language) -> Self {
+        self.default_language = language;
+        self
+    }
+
+    /// Serves repeated texts from `cache` instead of calling the backend.
+    pub fn with_cache(mut self, cache: AnalysisCache) -> Self {
+        self.cache = Some(Mutex::new(cache));
+        self
+    }
+
+    /// Analyzes input over the size limit in sentence-aligned chunks instead
+    /// of rejecting it; the result carries the per-chunk breakdown.
+    pub fn with_chunking(mut self, config: ChunkingConfig) -> Self {
+        self.chunking = Some(config);
+        self
+    }
+
+    /// Also scores every sentence of the input on its own, so callers can
+    /// see which sentences drove the overall result.
+    ///
+    /// Sentences are scored locally with the bundled lexicon for the text's
+    /// language, whatever the backend: the breakdown makes no backend calls,
+    /// so it costs no requests and never fails or slows down the analysis
+    /// beyond the scoring itself. Languages without a bundled lexicon get no
+    /// breakdown, and multi-sentence scores may differ from the backend's.
+    pub fn with_sentence_breakdown(mut self, enabled: bool) -> Self {
+        self.sentence_lexicons = enabled.then(|| {
+            Language::ALL
+                .into_iter()
+                .filter_map(|language| Some((language, Lexicon::bundled(language)?)))
+                .collect()
+        });
+        self
+    }
+
+    /// Also scores anger, joy, sadness, fear, surprise and trust with
+    /// `lexicon`, locally, for text in the lexicon's language.
+    pub fn with_emotions(mut self, lexicon: EmotionLexicon) -> Self {
+        self.emotions = Some(lexicon);
+        self
+    }
+
+    /// Whether emails, phone numbers, card numbers and IBANs are replaced with
+    /// placeholders before text reaches the backend; on by default.
+    pub fn with_pii_redaction(mut self, enabled: bool) -> Self {
+        self.redact_pii = enabled;
+        self
+    }
+
+    /// Hit and miss counts, if a cache is configured.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.lock().unwrap().stats())
+    }
+
+    /// Writes the cache to its persistence file, if both are configured.
+    pub fn save_cache(&self) -> io::Result<()> {
+        match &self.cache {
+            Some(cache) => cache.lock().unwrap().save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Name of the backend results come from, as used in metrics.
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    pub fn breaker_state(&self) -> BreakerState {
+        self.with_breaker(|breaker| breaker.state())
+    }
+
+    /// Latency, error, cache and breaker metrics recorded by this analyzer.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Runs `f` on the breaker and records any state change it caused
+    fn with_breaker<T>(&self, f: impl FnOnce(&mut CircuitBreaker) -> T) -> T {
+        let mut breaker = self.circuit_breaker.lock().unwrap();
+        let result = f(&mut breaker);
+        self.metrics.observe_breaker(breaker.state());
+        result
+    }
+
+    // Input validation - Rust's type system at work
+    pub fn sanitize_input(&self, input: &str) -> Result<String, AnalysisError> {
+        self.sanitize_with_report(input).map(|sanitized| sanitized.text)
+    }
+
+    /// Sanitizes `input` and reports which characters were removed or normalized.
+    pub fn sanitize_with_report(&self, input: &str) -> Result<Sanitized, AnalysisError> {
+        self.sanitization.apply(input)
+    }
+
+    // Main analysis function - all safety patterns together
+    /// Each call gets a fresh request ID, recorded on the returned analysis and
+    /// on an `analyze_text` tracing span that also covers the backend's events.
+    pub async fn analyze_text(&self, input: &str) -> Result<TextAnalysis, AnalysisError> {
+        self.analyze_until(input, self.deadline_from_now()).await
+    }
+
+    fn deadline_from_now(&self) -> Option<Deadline> {
+        self.deadline.map(|after| Deadline { at: tokio::time::Instant::now() + after, after })
+    }
+
+    async fn analyze_until(&self, input: &str, deadline: Option<Deadline>) -> Result<TextAnalysis, AnalysisError> {
+        let request_id = Uuid::new_v4();
+        let span = tracing::info_span!(
+            "analyze_text",
+            %request_id,
+            input_bytes = input.len(),
+            language = tracing::field::Empty
+        );
+        let request = Request { id: request_id, deadline };
+        let result = self.analyze_in_span(input, request).instrument(span).await;
+        self.metrics.record_request();
+        if let Err(e) = &result {
+            self.metrics.record_error(e);
+        }
+        result
+    }
+
+    async fn analyze_in_span(&self, input: &str, request: Request) -> Result<TextAnalysis, AnalysisError> {
+        let start_time = Instant::now();
+
+        // Step 1: Input validation
+        let mut analysis = match self.sanitize_with_report(input) {
+            Err(AnalysisError::InputTooLarge { .. }) if self.chunking.is_some() => {
+                self.analyze_chunked(input, request, start_time).await?
+            }
+            result => self.analyze_sanitized(&result?, request, start_time).await?,
+        };
+
+        if let Some(lexicons) = &self.sentence_lexicons {
+            analysis.sentences = self.analyze_sentences(lexicons, input, &analysis);
+            analysis.processing_time = start_time.elapsed();
+        }
+        if let Some(lexicon) = &self.emotions {
+            let language = analysis.language.map_or(self.default_language, |detection| detection.language);
+            if language == lexicon.language() {
+                analysis.emotions = self.local_text(input).map(|text| lexicon.score(&text));
+            }
+        }
+        Ok(analysis)
+    }
+
+    async fn analyze_sanitized(
+        &self,
+        sanitized: &Sanitized,
+        request: Request,
+        start_time: Instant,
+    ) -> Result<TextAnalysis, AnalysisError> {
+        // Step 2: Language identification; short or ambiguous text is taken to
+        // be in the default language rather than rejected
+        let language = self
+            .language_detector
+            .detect(&sanitized.text)
+            .unwrap_or(LanguageDetection { language: self.default_language, confidence: 0.0 });
+        self.analyze_as(sanitized, language, request, start_time).await
+    }
+
+    async fn analyze_as(
+        &self,
+        sanitized: &Sanitized,
+        language: LanguageDetection,
+        request: Request,
+        start_time: Instant,
+    ) -> Result<TextAnalysis, AnalysisError> {
+        tracing::Span::current().record("language", language.language.code());
+        if !self.backend.supports(language.language) {
+            return Err(AnalysisError::UnsupportedLanguage { language: language.language });
+        }
+
+        // Personal data is replaced before the text reaches the cache key or
+        // the backend, so neither ever holds it
+        let redacted = self.redact_pii.then(|| redact::redact(&sanitized.text));
+        let text = redacted.as_ref().map_or(sanitized.text.as_str(), |r| r.text.as_str());
+        // Mentions come back as offsets into `text` and are handed on as
+        // offsets into the input; the cache keeps them as they came back
+        let offsets: Vec<&OffsetMap> = redacted.iter().map(|r| &r.offsets).chain([&sanitized.offsets]).collect();
+        let redactions = redacted.as_ref().map(|r| r.counts.clone()).unwrap_or_default();
+        if !redactions.is_empty() {
+            tracing::debug!(?redactions, "personal data redacted");
+        }
+
+        // Cached results are served even while the breaker is open
+        let cache_key = self.cache.as_ref().map(|_| {
+            AnalysisCache::key(&self.backend_identity, language.language, text)
+        });
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            let cached = cache.lock().unwrap().get(key);
+            self.metrics.record_cache_lookup(cached.is_some());
+            if let Some(mut analysis) = cached {
+                analysis.processing_time = start_time.elapsed();
+                analysis.request_id = request.id;
+                analysis.attempts = 0;
+                map_mentions(&mut analysis, &offsets);
+                tracing::debug!("served from cache");
+                return Ok(analysis);
+            }
+        }
+
+        // Out of time before reaching the backend, which is not its fault
+        if let Some(deadline) = request.deadline {
+            if tokio::time::Instant::now() >= deadline.at {
+                return Err(AnalysisError::DeadlineExceeded { after: deadline.after });
+            }
+        }
+
+        // Step 3: Circuit breaker check (the lock is never held across an await)
+        let Some(admission) = Admission::acquire(self) else {
+            tracing::warn!("circuit breaker open, request rejected");
+            return Err(AnalysisError::CircuitBreakerOpen);
+        };
+
+        // Step 4: Safe AI processing through the configured backend, retrying
+        // transient failures before the breaker hears about them. Timeouts
+        // count as failures; retries that could not finish in time are skipped.
+        let mut attempt = 1;
+        let result = loop {
+            let call_started = Instant::now();
+            let result = self.call_backend(text, language.language, request.deadline).await;
+            self.metrics.record_backend_call(self.backend.name(), call_started.elapsed());
+            if let Err(e) = &result {
+                let delay = self.retry.delay_before_retry(attempt, e).filter(|delay| {
+                    request.deadline.is_none_or(|deadline| tokio::time::Instant::now() + *delay < deadline.at)
+                });
+                if let Some(delay) = delay {
+                    tracing::debug!(attempt, kind = e.kind(), error = %e, ?delay, "retrying backend call");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    continue;
+                }
+            }
+            break result;
+        };
+        match result {
+            Ok(mut analysis) => {
+                admission.settle(CircuitBreaker::record_success);
+                analysis.processing_time = start_time.elapsed();
+                analysis.request_id = request.id;
+                analysis.language = Some(language);
+                analysis.attempts = attempt;
+                analysis.redactions = redactions;
+                tracing::debug!(elapsed = ?analysis.processing_time, attempts = attempt, "analysis complete");
+                if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                    cache.lock().unwrap().insert(key, analysis.clone());
+                }
+                map_mentions(&mut analysis, &offsets);
+                Ok(analysis)
+            }
+            Err(e) => {
+                admission.settle(CircuitBreaker::record_failure);
+                tracing::warn!(kind = e.kind(), error = %e, attempts = attempt, "backend call failed");
+                Err(e)
+            }
+        }
+    }
+
+    // One backend call, bounded by the call timeout and the request deadline;
+    // whichever comes first decides the error
+    async fn call_backend(
+        &self,
+        text: &str,
+        language: Language,
+        deadline: Option<Deadline>,
+    ) -> Result<TextAnalysis, AnalysisError> {
+        let mut limit = (
+            tokio::time::Instant::now() + self.call_timeout,
+            AnalysisError::Timeout { after: self.call_timeout },
+        );
+        if let Some(deadline) = deadline.filter(|deadline| deadline.at < limit.0) {
+            limit = (deadline.at, AnalysisError::DeadlineExceeded { after: deadline.after });
+        }
+        let (at, error) = limit;
+        tokio::time::timeout_at(at, self.backend.analyze(text, language)).await.unwrap_or(Err(error))
+    }
+
+    // Each chunk goes through the usual language, cache and breaker steps;
+    // the first failing chunk fails the whole input
+    async fn analyze_chunked(
+        &self,
+        input: &str,
+        request: Request,
+        start_time: Instant,
+    ) -> Result<TextAnalysis, AnalysisError> {
+        let config = self.chunking.as_ref().expect("chunking is enabled");
+        let policy = &self.sanitization;
+        let size = policy.limit_unit.measure(input);
+        let limit = policy.max_input.saturating_mul(config.max_chunks);
+        if size > limit {
+            return Err(AnalysisError::InputTooLarge { limit, actual: size, unit: policy.limit_unit });
+        }
+
+        let chunks = chunking::split(input, policy.max_input, policy.limit_unit);
+        tracing::debug!(chunks = chunks.len(), "analyzing input in chunks");
+        let tasks: Vec<_> = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let span = tracing::info_span!(
+                    "chunk",
+                    index,
+                    start = chunk.start,
+                    end = chunk.end,
+                    language = tracing::field::Empty
+                );
+                async move {
+                    // A chunk of only stripped characters has nothing to analyze
+                    let sanitized = match self.sanitize_with_report(chunk.text) {
+                        Err(AnalysisError::EmptyInput) => return Ok(None),
+                        result => result?,
+                    };
+                    let analysis = self.analyze_sanitized(&sanitized, request, Instant::now()).await?;
+                    Ok(Some(ChunkAnalysis { start: chunk.start, end: chunk.end, analysis }))
+                }
+                .instrument(span)
+            })
+            .collect();
+        let results: Vec<Result<Option<ChunkAnalysis>, AnalysisError>> =
+            stream::iter(tasks).buffered(self.batch_concurrency).collect().await;
+
+        let mut analyzed = Vec::new();
+        for result in results {
+            analyzed.extend(result?);
+        }
+        if analyzed.is_empty() {
+            return Err(AnalysisError::EmptyInput);
+        }
+
+        let mut analysis = chunking::aggregate(input, analyzed, config.max_topics);
+        analysis.processing_time = start_time.elapsed();
+        analysis.request_id = request.id;
+        if let Some(language) = analysis.language {
+            tracing::Span::current().record("language", language.language.code());
+        }
+        Ok(analysis)
+    }
+
+    // `input` as the backend would see it, sanitized and redacted, for scoring
+    // locally; the size limit is left out, as chunked input is over it
+    fn local_text(&self, input: &str) -> Option<String> {
+        let policy = SanitizationPolicy { max_input: usize::MAX, ..self.sanitization.clone() };
+        let text = policy.apply(input).ok()?.text;
+        Some(if self.redact_pii { redact::redact(&text).text } else { text })
+    }
+
+    // Scores each sentence of `input` as the language of the whole text, which
+    // a short sentence alone is often too brief to reveal. Scoring is local, so
+    // it stays out of the breaker's accounting and cannot fail the analysis
+    fn analyze_sentences(
+        &self,
+        lexicons: &BTreeMap<Language, Lexicon>,
+        input: &str,
+        overall: &TextAnalysis,
+    ) -> Vec<SentenceSentiment> {
+        let sentences = text::sentences(input);
+        if let [sentence] = sentences.as_slice() {
+            let sentiment = overall.sentiment.clone();
+            return vec![SentenceSentiment { start: sentence.start, end: sentence.end, sentiment }];
+        }
+
+        let language = overall.language.map_or(self.default_language, |detection| detection.language);
+        let Some(lexicon) = lexicons.get(&language) else {
+            tracing::debug!(%language, "no lexicon to score sentences with");
+            return Vec::new();
+        };
+        sentences
+            .into_iter()
+            .filter_map(|sentence| {
+                // Sentences of only stripped characters, or longer than the
+                // limit in chunked input, get no score of their own
+                let sanitized = self.sanitize_input(sentence.text).ok()?;
+                let text = if self.redact_pii { redact::redact(&sanitized).text } else { sanitized };
+                let sentiment = lexicon.score(&text).sentiment;
+                Some(SentenceSentiment { start: sentence.start, end: sentence.end, sentiment })
+            })
+            .collect()
+    }
+
+    /// Analyzes `texts` concurrently, at most `batch_concurrency` at a time.
+    ///
+    /// Results are returned in input order. All tasks share one circuit breaker,
+    /// so once it opens the texts not yet started fail fast with
+    /// `CircuitBreakerOpen` instead of reaching the backend. They also share
+    /// one deadline, counted from this call.
+    pub async fn analyze_batch(&self, texts: &[&str]) -> Vec<Result<TextAnalysis, AnalysisError>> {
+        // Futures are lazy, so creating them up front starts no work; keeping the
+        // closure out of the returned future lets axum handlers prove it is Send
+        let deadline = self.deadline_from_now();
+        let tasks: Vec<_> = texts.iter().map(|text| self.analyze_until(text, deadline)).collect();
+        stream::iter(tasks)
+            .buffered(self.batch_concurrency)
+            .collect()
+            .await
+    }
+}
+
+// Maps topic mentions through `offsets` in turn, from the text the backend
+// analyzed back to the input
+fn map_mentions(analysis: &mut TextAnalysis, offsets: &[&OffsetMap]) {
+    for mention in analysis.key_topics.iter_mut().flat_map(|topic| &mut topic.mentions) {
+        let range = offsets.iter().fold(mention.start..mention.end, |range, map| map.original_range(range));
+        *mention = Mention { start: range.start, end: range.end };
+    }
+}
+
+// What every step of one `analyze_text` call needs to know about it
+#[derive(Debug, Clone, Copy)]
+struct Request {
+    id: Uuid,
+    deadline: Option<Deadline>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Deadline {
+    at: tokio::time::Instant,
+    // The configured limit, for the error message
+    after: Duration,
+}
+
+// A call let through by the breaker. If the analysis is dropped before the
+// call's outcome is recorded, e.g. because the client disconnected, a
+// half-open probe slot it holds is given back instead of being lost.
+struct Admission<'a> {
+    analyzer: &'a TextAnalyzer,
+    // Taken once the outcome has been recorded
+    permit: Option<Permit>,
+}
+
+impl<'a> Admission<'a> {
+    fn acquire(analyzer: &'a TextAnalyzer) -> Option<Self> {
+        let permit = analyzer.with_breaker(CircuitBreaker::try_acquire)?;
+        Some(Self { analyzer, permit: Some(permit) })
+    }
+
+    fn settle(mut self, record: impl FnOnce(&mut CircuitBreaker, Permit)) {
+        if let Some(permit) = self.permit.take() {
+            self.analyzer.with_breaker(|breaker| record(breaker, permit));
+        }
+    }
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take() {
+            tracing::debug!("analysis abandoned during a backend call");
+            self.analyzer.with_breaker(|breaker| breaker.release(permit));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Fault, LexiconBackend, ScriptStep, ScriptedBackend};
+    use crate::cache::CacheConfig;
+    use crate::circuit_breaker::{CircuitBreakerConfig, TokioClock};
+    use crate::emotion::Emotion;
+    use crate::redact::PiiKind;
+    use crate::sanitize::LimitUnit;
+    use async_trait::async_trait;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_input_validation() {
+        let analyzer = TextAnalyzer::new().unwrap();
+        
+        // Test empty input
+        assert!(matches!(
+            analyzer.sanitize_input(""),
+            Err(AnalysisError::EmptyInput)
+        ));
+        
+        // Test valid input
+        let result = analyzer.sanitize_input("Hello, world!");
+        assert!(result.is_ok());
+
+        // Test configured size limit
+        let analyzer = analyzer.with_max_input_size(5);
+        assert!(matches!(
+            analyzer.sanitize_input("Hello, world!"),
+            Err(AnalysisError::InputTooLarge { limit: 5, actual: 13, unit: LimitUnit::Chars })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_analysis() {
+        let analyzer = TextAnalyzer::new().unwrap();
+        let result = analyzer.analyze_text("I love this!").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_each_call_gets_a_request_id() {
+        let analyzer = TextAnalyzer::with_backend(Box::new(MockBackend::with_latency(Duration::ZERO)));
+        let first = analyzer.analyze_text("I love this!").await.unwrap();
+        let second = analyzer.analyze_text("I love this!").await.unwrap();
+
+        assert!(!first.request_id.is_nil());
+        assert_ne!(first.request_id, second.request_id);
+    }
+
+    #[tokio::test]
+    async fn test_language_is_detected_and_checked() {
+        let analyzer = TextAnalyzer::with_backend(Box::new(MockBackend::with_latency(Duration::ZERO)));
+
+        let spanish = analyzer.analyze_text("Me encanta, la entrega fue rápida y el precio es excelente").await.unwrap();
+        assert_eq!(spanish.language.unwrap().language, Language::Spanish);
+        assert!(spanish.sentiment.positive > 0.5);
+
+        // Too short to identify, so the default language is assumed
+        let short = analyzer.analyze_text("I love it").await.unwrap();
+        assert_eq!(short.language.unwrap().language, Language::English);
+        assert_eq!(short.language.unwrap().confidence, 0.0);
+
+        // Rejected before the backend is called, so the breaker is untouched
+        for _ in 0..3 {
+            assert!(matches!(
+                analyzer.analyze_text("Je suis très déçu, le colis est arrivé en retard et abîmé.").await,
+                Err(AnalysisError::UnsupportedLanguage { language: Language::French })
+            ));
+        }
+        assert_eq!(analyzer.breaker_state(), BreakerState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cache_serves_repeated_texts() {
+        let analyzer = TextAnalyzer::new().unwrap().with_cache(AnalysisCache::new(CacheConfig::default()));
+        let first = analyzer.analyze_text("I love this!").await.unwrap();
+
+        // Sanitization runs first, so equivalent inputs share an entry
+        let start = tokio::time::Instant::now();
+        let second = analyzer.analyze_text("I love this!\u{200B}").await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert_eq!(second.sentiment.positive, first.sentiment.positive);
+        assert_ne!(second.request_id, first.request_id);
+
+        analyzer.analyze_text("I hate this!").await.unwrap();
+        assert_eq!(analyzer.cache_stats(), Some(CacheStats { hits: 1, misses: 2, entries: 2 }));
+    }
+
+    #[tokio::test]
+    async fn test_long_input_is_chunked_when_enabled() {
+        let text = "I love this product. The support team was excellent! Shipping was awful and slow.";
+        let analyzer = TextAnalyzer::with_backend(Box::new(MockBackend::with_latency(Duration::ZERO)))
+            .with_max_input_size(55);
+        assert!(matches!(analyzer.analyze_text(text).await, Err(AnalysisError::InputTooLarge { .. })));
+
+        let analyzer = analyzer.with_chunking(ChunkingConfig::default());
+        let analysis = analyzer.analyze_text(text).await.unwrap();
+        let spans: Vec<&str> = analysis.chunks.iter().map(|c| &text[c.start..c.end]).collect();
+        assert_eq!(spans, ["I love this product. The support team was excellent!", "Shipping was awful and slow."]);
+        assert!(analysis.chunks.iter().all(|c| c.analysis.request_id == analysis.request_id));
+        assert!(analysis.sentiment.positive > 0.0 && analysis.sentiment.negative > 0.0);
+
+        let analyzer = analyzer.with_chunking(ChunkingConfig { max_chunks: 1, ..Default::default() });
+        assert!(matches!(
+            analyzer.analyze_text(text).await,
+            Err(AnalysisError::InputTooLarge { limit: 55, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sentence_breakdown_offsets_into_input() {
+        let text = "\u{202E}The interface is great. Support was terrible!";
+        let analyzer = TextAnalyzer::with_backend(Box::new(LexiconBackend::default()));
+        assert!(analyzer.analyze_text(text).await.unwrap().sentences.is_empty());
+
+        let analysis = analyzer.with_sentence_breakdown(true).analyze_text(text).await.unwrap();
+        let spans: Vec<&str> = analysis.sentences.iter().map(|s| &text[s.start..s.end]).collect();
+        assert_eq!(spans, ["\u{202E}The interface is great.", "Support was terrible!"]);
+        assert!(analysis.sentences[0].sentiment.positive > analysis.sentences[0].sentiment.negative);
+        assert!(analysis.sentences[1].sentiment.negative > analysis.sentences[1].sentiment.positive);
+    }
+
+    #[tokio::test]
+    async fn test_sentence_breakdown_makes_no_backend_calls() {
+        // Only the overall call reaches the backend; a failing one after it
+        // would otherwise fail the breakdown and count against the breaker
+        let backend = ScriptedBackend::new([ScriptStep::succeed()]).then(ScriptStep::fail(Fault::Api));
+        let calls = backend.call_counter();
+        let analyzer = scripted_analyzer(backend, 1).with_sentence_breakdown(true);
+
+        let text = "The interface is great. Support was terrible!";
+        let analysis = analyzer.analyze_text(text).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(analysis.sentences.len(), 2);
+        assert!(analysis.sentences[0].sentiment.positive > analysis.sentences[0].sentiment.negative);
+        assert_eq!(analyzer.breaker_state(), BreakerState::Closed);
+
+        // No bundled French lexicon, so no breakdown, but the analysis stands
+        let french = "Le service était vraiment excellent. La livraison est arrivée en retard!";
+        let analyzer = scripted_analyzer(ScriptedBackend::new([]), 1).with_sentence_breakdown(true);
+        assert!(analyzer.analyze_text(french).await.unwrap().sentences.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mentions_point_into_the_input() {
+        let text = "\u{202E}Mail jane@example.com: the battery died. I charged the battery!";
+        let cache = AnalysisCache::open(CacheConfig::default()).unwrap();
+        let analyzer = TextAnalyzer::with_backend(Box::new(LexiconBackend::default())).with_cache(cache);
+
+        // Fresh and cached results alike
+        for _ in 0..2 {
+            let analysis = analyzer.analyze_text(text).await.unwrap();
+            let mentioned: Vec<(&str, &str)> = analysis
+                .key_topics
+                .iter()
+                .flat_map(|topic| topic.mentions.iter().map(|m| (topic.name.as_str(), &text[m.start..m.end])))
+                .collect();
+            assert!(mentioned.contains(&("battery died", "battery died")));
+            assert!(mentioned.iter().all(|(name, found)| found.to_lowercase() == *name), "{:?}", mentioned);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_emotions_are_scored_when_asked_for() {
+        let text = "I am furious, support was rude and the refund is a scam.";
+        let analyzer = TextAnalyzer::with_backend(Box::new(LexiconBackend::default()));
+        let analysis = analyzer.analyze_text(text).await.unwrap();
+        assert!(analysis.emotions.is_none());
+        assert!(serde_json::to_value(&analysis).unwrap().get("emotions").is_none());
+
+        let analyzer = analyzer.with_emotions(EmotionLexicon::english());
+        let emotions = analyzer.analyze_text(text).await.unwrap().emotions.unwrap();
+        assert_eq!(emotions.dominant(), Some(Emotion::Anger));
+
+        // Text in another language than the lexicon's is not scored
+        let spanish = "El servicio fue terrible y el producto no funciona, estoy muy enojado con la tienda.";
+        assert!(analyzer.analyze_text(spanish).await.unwrap().emotions.is_none());
+
+        // Emotions are scored on the redacted text, like everything else
+        let lexicon = EmotionLexicon::parse("angry\tanger\n").unwrap();
+        let analyzer = TextAnalyzer::with_backend(Box::new(LexiconBackend::default())).with_emotions(lexicon);
+        let text = "Please write to angry.customer@example.com about the order.";
+        assert_eq!(analyzer.analyze_text(text).await.unwrap().emotions.unwrap().dominant(), None);
+        let analyzer = analyzer.with_pii_redaction(false);
+        assert_eq!(analyzer.analyze_text(text).await.unwrap().emotions.unwrap().dominant(), Some(Emotion::Anger));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_runs_concurrently_in_order() {
+        let analyzer = TextAnalyzer::new().unwrap().with_batch_concurrency(5);
+        let texts = ["I love it", "", "This is terrible", "ok", "great", "bad", "fine", "meh", "hate", "amazing"];
+
+        let start = tokio::time::Instant::now();
+        let results = analyzer.analyze_batch(&texts).await;
+
+        // Ten 100ms calls, five at a time
+        assert_eq!(start.elapsed(), std::time::Duration::from_millis(200));
+        assert_eq!(results.len(), texts.len());
+        assert!(results[0].as_ref().unwrap().sentiment.positive > 0.5);
+        assert!(matches!(results[1], Err(AnalysisError::EmptyInput)));
+        assert!(results[2].as_ref().unwrap().sentiment.negative > 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_batch_stops_when_breaker_opens() {
+        let analyzer = TextAnalyzer::with_backend(Box::new(ScriptedBackend::new([]).then(ScriptStep::fail(Fault::Api)))).with_batch_concurrency(1);
+        let results = analyzer.analyze_batch(&["a"; 6]).await;
+
+        assert!(results[..3].iter().all(|r| matches!(r, Err(AnalysisError::ApiError(_)))));
+        assert!(results[3..].iter().all(|r| matches!(r, Err(AnalysisError::CircuitBreakerOpen))));
+    }
+
+    #[tokio::test]
+    async fn test_backend_failures_open_breaker() {
+        let analyzer = TextAnalyzer::with_backend(Box::new(ScriptedBackend::new([]).then(ScriptStep::fail(Fault::Api))));
+        for _ in 0..3 {
+            assert!(matches!(
+                analyzer.analyze_text("hello").await,
+                Err(AnalysisError::ApiError(_))
+            ));
+        }
+        assert!(matches!(
+            analyzer.analyze_text("hello").await,
+            Err(AnalysisError::CircuitBreakerOpen)
+        ));
+    }
+
+    // Records the text it is sent, then answers like the mock backend
+    struct RecordingBackend {
+        texts: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl AnalysisBackend for RecordingBackend {
+        async fn analyze(&self, text: &str, language: Language) -> Result<TextAnalysis, AnalysisError> {
+            self.texts.lock().unwrap().push(text.to_string());
+            MockBackend::with_latency(Duration::ZERO).analyze(text, language).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pii_is_redacted_before_the_backend() {
+        let text = "Great service, write to jane@example.com or call +44 20 7946 0958 any time";
+        let texts = Arc::new(Mutex::new(Vec::new()));
+        let analyzer = TextAnalyzer::with_backend(Box::new(RecordingBackend { texts: texts.clone() }));
+
+        let analysis = analyzer.analyze_text(text).await.unwrap();
+        assert_eq!(
+            texts.lock().unwrap().last().unwrap(),
+            "Great service, write to [EMAIL] or call [PHONE] any time"
+        );
+        assert_eq!(analysis.redactions, BTreeMap::from([(PiiKind::Email, 1), (PiiKind::Phone, 1)]));
+        assert!(analysis.key_topics.iter().all(|t| !["email", "phone"].contains(&t.name.to_lowercase().as_str())));
+        assert_eq!(serde_json::to_value(&analysis).unwrap()["redactions"]["email"], 1);
+
+        let analyzer = analyzer.with_pii_redaction(false);
+        let analysis = analyzer.analyze_text(text).await.unwrap();
+        assert_eq!(texts.lock().unwrap().last().unwrap(), text);
+        assert!(analysis.redactions.is_empty());
+    }
+
+    fn scripted_analyzer(backend: ScriptedBackend, failure_threshold: u32) -> TextAnalyzer {
+        let config = CircuitBreakerConfig { failure_threshold, ..Default::default() };
+        TextAnalyzer::with_backend(Box::new(backend))
+            .with_circuit_breaker(CircuitBreaker::with_clock(config, Arc::new(TokioClock)))
+            .with_retry_policy(RetryPolicy { jitter: false, ..RetryPolicy::default() })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_transient_failures_are_retried_before_the_breaker() {
+        let second = Duration::from_secs(1);
+        let backend = ScriptedBackend::new([
+            ScriptStep::fail(Fault::Timeout).after(second),
+            ScriptStep::fail(Fault::Http { status: 503 }).after(second),
+        ]);
+        let analyzer = scripted_analyzer(backend, 1);
+
+        let start = tokio::time::Instant::now();
+        let analysis = analyzer.analyze_text("I love this!").await.unwrap();
+        assert_eq!(analysis.attempts, 3);
+        // Two failed calls, then backoffs of 200ms and 400ms
+        assert_eq!(start.elapsed(), Duration::from_millis(2600));
+        assert_eq!(analyzer.breaker_state(), BreakerState::Closed);
+        assert_eq!(analyzer.metrics().snapshot().backend_latency["scripted"].count, 3);
+
+        // Out of attempts: the last error is returned and counted once
+        let analyzer = scripted_analyzer(ScriptedBackend::new(vec![ScriptStep::fail(Fault::Timeout); 3]), 1);
+        assert!(matches!(analyzer.analyze_text("I love this!").await, Err(AnalysisError::Timeout { .. })));
+        assert_eq!(analyzer.breaker_state(), BreakerState::Open);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_waits_as_long_as_the_backend_asks() {
+        let limited = Fault::RateLimited { retry_after: Some(Duration::from_secs(3)) };
+        let backend = ScriptedBackend::new([ScriptStep::fail(limited)]);
+        let calls = backend.call_counter();
+        let analyzer = scripted_analyzer(backend, 3);
+
+        let start = tokio::time::Instant::now();
+        assert_eq!(analyzer.analyze_text("hello").await.unwrap().attempts, 2);
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+
+        // A wait beyond the policy's max delay is not worth retrying
+        let too_long = Fault::RateLimited { retry_after: Some(Duration::from_secs(60)) };
+        let analyzer = scripted_analyzer(ScriptedBackend::new([ScriptStep::fail(too_long)]), 3);
+        assert!(matches!(analyzer.analyze_text("hello").await, Err(AnalysisError::RateLimited { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_breaker_opens_probes_and_recovers() {
+        let api = || ScriptStep::fail(Fault::Api).after(Duration::from_millis(50));
+        let backend = ScriptedBackend::new([api(), api(), api(), api()]);
+        let calls = backend.call_counter();
+        let analyzer = scripted_analyzer(backend, 3);
+
+        for _ in 0..3 {
+            assert!(matches!(analyzer.analyze_text("hello").await, Err(AnalysisError::ApiError(_))));
+        }
+        assert_eq!(analyzer.breaker_state(), BreakerState::Open);
+        assert!(matches!(analyzer.analyze_text("hello").await, Err(AnalysisError::CircuitBreakerOpen)));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // The cooldown passes on tokio's paused clock; a failed probe reopens
+        tokio::time::advance(CircuitBreakerConfig::default().cooldown).await;
+        assert_eq!(analyzer.breaker_state(), BreakerState::HalfOpen);
+        assert!(matches!(analyzer.analyze_text("hello").await, Err(AnalysisError::ApiError(_))));
+        assert_eq!(analyzer.breaker_state(), BreakerState::Open);
+
+        // The script has run out, so the next probe succeeds and closes it
+        tokio::time::advance(CircuitBreakerConfig::default().cooldown).await;
+        analyzer.analyze_text("hello").await.unwrap();
+        assert_eq!(analyzer.breaker_state(), BreakerState::Closed);
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+        let transitions: Vec<(BreakerState, BreakerState, u64)> = analyzer
+            .metrics()
+            .snapshot()
+            .breaker_transitions
+            .iter()
+            .map(|t| (t.from, t.to, t.count))
+            .collect();
+        assert_eq!(
+            transitions,
+            [
+                (BreakerState::Closed, BreakerState::Open, 1),
+                (BreakerState::Open, BreakerState::HalfOpen, 2),
+                (BreakerState::HalfOpen, BreakerState::Closed, 1),
+                (BreakerState::HalfOpen, BreakerState::Open, 1),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancelled_probe_gives_its_slot_back() {
+        let backend = ScriptedBackend::new([
+            ScriptStep::fail(Fault::Api),
+            ScriptStep::succeed().after(Duration::from_secs(10)),
+        ]);
+        let analyzer = scripted_analyzer(backend, 1);
+        assert!(analyzer.analyze_text("hello").await.is_err());
+        tokio::time::advance(CircuitBreakerConfig::default().cooldown).await;
+
+        // The caller gives up on the probe while it waits on the backend
+        let probe = tokio::time::timeout(Duration::from_secs(1), analyzer.analyze_text("hello")).await;
+        assert!(probe.is_err());
+        assert_eq!(analyzer.breaker_state(), BreakerState::HalfOpen);
+
+        // The next probe is admitted and closes the breaker
+        analyzer.analyze_text("hello").await.unwrap();
+        assert_eq!(analyzer.breaker_state(), BreakerState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_calls_outliving_the_closed_breaker_are_not_probes() {
+        let second = Duration::from_secs(1);
+        let backend = ScriptedBackend::new([
+            ScriptStep::succeed().after(35 * second),
+            ScriptStep::succeed().after(35 * second),
+            ScriptStep::fail(Fault::Api),
+            ScriptStep::succeed().after(60 * second),
+        ]);
+        let analyzer = scripted_analyzer(backend, 1);
+
+        // Two calls are admitted while closed, then the breaker trips and,
+        // after the cooldown, admits a probe while they are still running
+        let mut dropped = Box::pin(analyzer.analyze_text("dropped"));
+        let mut finished = Box::pin(analyzer.analyze_text("finished"));
+        assert!(futures::poll!(&mut dropped).is_pending());
+        assert!(futures::poll!(&mut finished).is_pending());
+        assert!(analyzer.analyze_text("hello").await.is_err());
+        tokio::time::advance(30 * second).await;
+        let mut probe = Box::pin(analyzer.analyze_text("probe"));
+        assert!(futures::poll!(&mut probe).is_pending());
+
+        // Dropping one gives back no probe slot
+        drop(dropped);
+        assert!(matches!(analyzer.analyze_text("hello").await, Err(AnalysisError::CircuitBreakerOpen)));
+
+        // The other succeeding does not close the breaker; only the probe can
+        finished.await.unwrap();
+        assert_eq!(analyzer.breaker_state(), BreakerState::HalfOpen);
+        probe.await.unwrap();
+        assert_eq!(analyzer.breaker_state(), BreakerState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hung_backend_times_out_and_trips_breaker() {
+        let hung = ScriptedBackend::new([]).then(ScriptStep::succeed().after(Duration::from_secs(3600)));
+        let analyzer = scripted_analyzer(hung, 1).with_call_timeout(Duration::from_secs(2));
+
+        let start = tokio::time::Instant::now();
+        let result = analyzer.analyze_text("hello").await;
+        assert!(matches!(result, Err(AnalysisError::Timeout { after }) if after == Duration::from_secs(2)));
+        // Three timed-out attempts with backoffs of 200ms and 400ms between them
+        assert_eq!(start.elapsed(), Duration::from_millis(6600));
+        assert_eq!(analyzer.breaker_state(), BreakerState::Open);
+        assert_eq!(analyzer.metrics().snapshot().errors["timeout"], 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_cuts_retries_short() {
+        let hung = ScriptedBackend::new([]).then(ScriptStep::succeed().after(Duration::from_secs(3600)));
+        let calls = hung.call_counter();
+        let analyzer = scripted_analyzer(hung, 1)
+            .with_call_timeout(Duration::from_secs(2))
+            .with_deadline(Duration::from_secs(3));
+
+        let start = tokio::time::Instant::now();
+        assert!(matches!(analyzer.analyze_text("hello").await, Err(AnalysisError::DeadlineExceeded { .. })));
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(analyzer.breaker_state(), BreakerState::Open);
+
+        // Too little time left for the backoff: the timeout stands
+        let hung = ScriptedBackend::new([]).then(ScriptStep::succeed().after(Duration::from_secs(3600)));
+        let analyzer = scripted_analyzer(hung, 1)
+            .with_call_timeout(Duration::from_secs(2))
+            .with_deadline(Duration::from_millis(2100));
+        assert!(matches!(analyzer.analyze_text("hello").await, Err(AnalysisError::Timeout { .. })));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_shares_one_deadline() {
+        let backend = ScriptedBackend::new([]).then(ScriptStep::succeed().after(Duration::from_millis(100)));
+        let calls = backend.call_counter();
+        let analyzer = scripted_analyzer(backend, u32::MAX)
+            .with_deadline(Duration::from_millis(250))
+            .with_batch_concurrency(1);
+
+        let start = tokio::time::Instant::now();
+        let results = analyzer.analyze_batch(&["text"; 5]).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(250));
+        assert!(results[..2].iter().all(Result::is_ok));
+        assert!(results[2..].iter().all(|r| matches!(r, Err(AnalysisError::DeadlineExceeded { .. }))));
+        // Only the third text reached the backend before time ran out
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_under_random_failures_is_reproducible() {
+        let run = || async {
+            let backend = ScriptedBackend::failure_rate(0.5, Fault::Transport, 42)
+                .then(ScriptStep::succeed().after(Duration::from_millis(100)));
+            let analyzer = scripted_analyzer(backend, u32::MAX).with_batch_concurrency(1);
+            let results = analyzer.analyze_batch(&["text"; 20]).await;
+            results.iter().map(|r| r.as_ref().map(|a| a.attempts).map_err(AnalysisError::kind)).collect::<Vec<_>>()
+        };
+
+        let first = run().await;
+        assert_eq!(first, run().await);
+        assert!(first.iter().any(|r| matches!(r, Ok(attempts) if *attempts > 1)));
+        assert!(first.iter().all(|r| matches!(r, Ok(_) | Err("transport"))));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_track_errors_latency_and_breaker() {
+        let analyzer = TextAnalyzer::with_backend(Box::new(ScriptedBackend::new([]).then(ScriptStep::fail(Fault::Api))));
+        for _ in 0..4 {
+            let _ = analyzer.analyze_text("hello").await;
+        }
+        let _ = analyzer.analyze_text("").await;
+
+        let snapshot = analyzer.metrics().snapshot();
+        assert_eq!(snapshot.requests, 5);
+        assert_eq!(snapshot.errors["api_error"], 3);
+        assert_eq!(snapshot.errors["circuit_breaker_open"], 1);
+        assert_eq!(snapshot.errors["empty_input"], 1);
+        assert_eq!(snapshot.backend_latency["scripted"].count, 3);
+        assert_eq!(snapshot.breaker_state, Some(BreakerState::Open));
+        assert_eq!(snapshot.breaker_transitions.len(), 1);
+        assert_eq!(snapshot.breaker_transitions[0].to, BreakerState::Open);
+    }
+}
@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use super::AnalysisBackend;
+use crate::analysis::TextAnalysis;
+use crate::cache::digest;
+use crate::confidence::{self, ConfidenceEvidence};
+use crate::error::AnalysisError;
+use crate::language::Language;
+use crate::sentiment::Lexicon;
+use crate::topics::{Taxonomy, TopicExtractor};
+
+/// Local, offline backend scoring sentiment against a word lexicon per language.
+pub struct LexiconBackend {
+    models: BTreeMap<Language, LanguageModel>,
+    taxonomy: Taxonomy,
+}
+
+#[derive(Hash)]
+struct LanguageModel {
+    lexicon: Lexicon,
+    topics: TopicExtractor,
+}
+
+impl LexiconBackend {
+    /// A backend for English text only, scored with `lexicon`.
+    pub fn new(lexicon: Lexicon) -> Self {
+        Self { models: BTreeMap::new(), taxonomy: Taxonomy::english() }
+            .with_lexicon(Language::English, lexicon)
+    }
+
+    /// Scores `language` with `lexicon`, replacing any lexicon it already had.
+    pub fn with_lexicon(mut self, language: Language, lexicon: Lexicon) -> Self {
+        let topics = topic_extractor(language, &lexicon, self.taxonomy.clone());
+        self.models.insert(language, LanguageModel { lexicon, topics });
+        self
+    }
+
+    /// Categorizes extracted topics with `taxonomy` instead of the bundled one.
+    pub fn with_taxonomy(mut self, taxonomy: Taxonomy) -> Self {
+        for (language, model) in &mut self.models {
+            model.topics = topic_extractor(*language, &model.lexicon, taxonomy.clone());
+        }
+        self.taxonomy = taxonomy;
+        self
+    }
+
+    /// Uses a VADER-style lexicon file instead of the bundled English one;
+    /// the other bundled languages are kept.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::default().with_lexicon(Language::English, Lexicon::load(path)?))
+    }
+}
+
+impl Default for LexiconBackend {
+    /// Every language with a bundled lexicon.
+    fn default() -> Self {
+        Self::new(Lexicon::english())
+            .with_lexicon(Language::Spanish, Lexicon::spanish())
+            .with_lexicon(Language::German, Lexicon::german())
+    }
+}
+
+#[async_trait]
+impl AnalysisBackend for LexiconBackend {
+    async fn analyze(&self, text: &str, language: Language) -> Result<TextAnalysis, AnalysisError> {
+        let model = self.models.get(&language).ok_or(AnalysisError::UnsupportedLanguage { language })?;
+        let score = model.lexicon.score(text);
+        let confidence = confidence::estimate(&ConfidenceEvidence {
+            sentiment: &score.sentiment,
+            total_tokens: score.total_tokens,
+            matched_tokens: Some(score.matched_tokens),
+            reported: None,
+        });
+
+        let key_topics = model.topics.extract_with_sentiment(text, |clause| model.lexicon.score(clause).sentiment);
+        Ok(TextAnalysis::new(score.sentiment, key_topics, confidence))
+    }
+
+    fn supports(&self, language: Language) -> bool {
+        self.models.contains_key(&language)
+    }
+
+    fn name(&self) -> &'static str {
+        "lexicon"
+    }
+
+    fn identity(&self) -> String {
+        format!("lexicon:{}", digest(&self.models))
+    }
+}
+
+// Sentiment words describe a topic rather than name it, so they end phrases
+fn topic_extractor(language: Language, lexicon: &Lexicon, taxonomy: Taxonomy) -> TopicExtractor {
+    TopicExtractor::for_language(language, taxonomy).exclude_words(lexicon.vocabulary().map(str::to_string))
+}
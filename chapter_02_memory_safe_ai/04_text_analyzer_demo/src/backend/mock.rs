@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::AnalysisBackend;
+use crate::analysis::{SentimentScore, TextAnalysis};
+use crate::cache::digest;
+use crate::confidence::{self, ConfidenceEvidence};
+use crate::error::AnalysisError;
+use crate::language::Language;
+use crate::text::tokenize;
+use crate::topics::{Taxonomy, TopicExtractor};
+
+// (language, positive words, negative words)
+const KEYWORDS: &[(Language, &[&str], &[&str])] = &[
+    (Language::English, &["love", "amazing", "great", "excellent"], &["hate", "terrible", "awful", "bad"]),
+    (Language::Spanish, &["encanta", "increíble", "genial", "excelente"], &["odio", "terrible", "horrible", "pésimo"]),
+    (Language::German, &["liebe", "toll", "großartig", "ausgezeichnet"], &["hasse", "schrecklich", "furchtbar", "schlecht"]),
+];
+
+/// Keyword-matching backend that simulates API latency; used for demos and tests.
+pub struct MockBackend {
+    latency: Duration,
+    topics: BTreeMap<Language, TopicExtractor>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::with_latency(Duration::from_millis(100))
+    }
+
+    pub fn with_latency(latency: Duration) -> Self {
+        Self { latency, topics: topic_extractors(Taxonomy::english()) }
+    }
+
+    /// Categorizes extracted topics with `taxonomy` instead of the bundled one.
+    pub fn with_taxonomy(mut self, taxonomy: Taxonomy) -> Self {
+        self.topics = topic_extractors(taxonomy);
+        self
+    }
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AnalysisBackend for MockBackend {
+    // Mock AI call demonstrating the patterns
+    async fn analyze(&self, text: &str, language: Language) -> Result<TextAnalysis, AnalysisError> {
+        let (Some(topics), Some((_, positive_words, negative_words))) =
+            (self.topics.get(&language), KEYWORDS.iter().find(|(l, _, _)| *l == language))
+        else {
+            return Err(AnalysisError::UnsupportedLanguage { language });
+        };
+
+        // Simulate API call delay
+        tokio::time::sleep(self.latency).await;
+
+        // Simple sentiment analysis for demo
+        let (sentiment, matched) = keyword_sentiment(text, positive_words, negative_words);
+
+        let confidence = confidence::estimate(&ConfidenceEvidence {
+            sentiment: &sentiment,
+            total_tokens: tokenize(text).len(),
+            matched_tokens: Some(matched),
+            reported: None,
+        });
+
+        let key_topics = topics
+            .extract_with_sentiment(text, |clause| keyword_sentiment(clause, positive_words, negative_words).0);
+        Ok(TextAnalysis::new(sentiment, key_topics, confidence))
+    }
+
+    fn supports(&self, language: Language) -> bool {
+        self.topics.contains_key(&language)
+    }
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn identity(&self) -> String {
+        format!("mock:{}", digest(&self.topics))
+    }
+}
+
+// Shares of positive and negative keywords found, with the number found
+fn keyword_sentiment(text: &str, positive_words: &[&str], negative_words: &[&str]) -> (SentimentScore, usize) {
+    let text_lower = text.to_lowercase();
+
+    let positive_count = positive_words.iter()
+        .filter(|&word| text_lower.contains(word))
+        .count() as f64;
+    let negative_count = negative_words.iter()
+        .filter(|&word| text_lower.contains(word))
+        .count() as f64;
+
+    let total = positive_count + negative_count;
+    let (pos, neg, neu) = if total > 0.0 {
+        (positive_count / total, negative_count / total, 0.1)
+    } else {
+        (0.2, 0.2, 0.6)
+    };
+
+    let sentiment = SentimentScore {
+        positive: pos,
+        negative: neg,
+        neutral: neu,
+    };
+    (sentiment, total as usize)
+}
+
+fn topic_extractors(taxonomy: Taxonomy) -> BTreeMap<Language, TopicExtractor> {
+    KEYWORDS
+        .iter()
+        .map(|(language, _, _)| (*language, TopicExtractor::for_language(*language, taxonomy.clone())))
+        .collect()
+}
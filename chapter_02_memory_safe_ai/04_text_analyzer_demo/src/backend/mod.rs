@@ -0,0 +1,49 @@
+//! Sentiment backends that `TextAnalyzer` can run sanitized text through.
+
+use async_trait::async_trait;
+
+use crate::analysis::TextAnalysis;
+use crate::error::AnalysisError;
+use crate::language::Language;
+
+mod lexicon;
+mod mock;
+mod openai;
+mod scripted;
+
+pub use lexicon::LexiconBackend;
+pub use mock::MockBackend;
+pub use openai::{OpenAiBackend, OpenAiConfig};
+pub use scripted::{Fault, ScriptStep, ScriptedBackend, StepOutcome};
+
+/// A source of `TextAnalysis` results for already-sanitized input.
+///
+/// Implementations only see text that has passed `TextAnalyzer::sanitize_input`
+/// and, unless disabled, PII redaction, and their failures are counted by the
+/// analyzer's circuit breaker. `processing_time`, `request_id`, `language`,
+/// `attempts` and `redactions` in the returned analysis are overwritten by
+/// the caller, and topic mentions, given as offsets into `text`, are mapped
+/// back to the caller's input.
+#[async_trait]
+pub trait AnalysisBackend: Send + Sync {
+    /// Analyzes `text`, which the caller has identified as `language`.
+    async fn analyze(&self, text: &str, language: Language) -> Result<TextAnalysis, AnalysisError>;
+
+    /// Whether the backend can score `language`; the analyzer rejects other
+    /// languages with `UnsupportedLanguage` rather than calling it.
+    fn supports(&self, _language: Language) -> bool {
+        true
+    }
+
+    /// Short label for the backend in metrics.
+    fn name(&self) -> &'static str {
+        let path = std::any::type_name::<Self>();
+        path.rsplit("::").next().unwrap_or(path)
+    }
+
+    /// Distinguishes backends, and configurations of one backend, that may
+    /// analyze the same text differently; part of every result cache key.
+    fn identity(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+}
@@ -0,0 +1,222 @@
+use async_trait::async_trait;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::AnalysisBackend;
+use crate::analysis::{SentimentScore, TextAnalysis, Topic};
+use crate::cache::digest;
+use crate::confidence::{self, ConfidenceEvidence};
+use crate::error::AnalysisError;
+use crate::language::Language;
+use crate::text::tokenize;
+use crate::topics::find_mentions;
+
+const SYSTEM_PROMPT: &str = "You are a sentiment analysis service. Reply with a single JSON object \
+of the form {\"sentiment\":{\"positive\":f,\"negative\":f,\"neutral\":f},\
+\"key_topics\":[{\"name\":s,\"relevance\":f,\"category\":s,\
+\"sentiment\":{\"positive\":f,\"negative\":f,\"neutral\":f}}],\"confidence\":f} \
+where every f is between 0 and 1, each set of three sentiment scores sums to 1, a topic's \
+sentiment describes how the text speaks about that topic, and every topic name is quoted \
+from the text.";
+
+/// Connection settings for an OpenAI-compatible chat completions endpoint.
+#[derive(Debug, Clone)]
+pub struct OpenAiConfig {
+    /// Base URL up to and including the API version, e.g. `https://api.openai.com/v1`.
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: String::new(),
+            model: "gpt-4o-mini".to_string(),
+        }
+    }
+}
+
+/// Backend that asks an OpenAI-compatible chat model for a JSON analysis.
+pub struct OpenAiBackend {
+    client: Client,
+    config: OpenAiConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: [Message<'a>; 2],
+    temperature: f32,
+    response_format: ResponseFormat,
+}
+
+#[derive(Debug, Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChoiceMessage {
+    content: String,
+}
+
+// The subset of `TextAnalysis` the model is asked to produce
+#[derive(Debug, Deserialize)]
+struct ModelAnalysis {
+    sentiment: SentimentScore,
+    #[serde(default)]
+    key_topics: Vec<Topic>,
+    confidence: f64,
+}
+
+impl OpenAiBackend {
+    pub fn new(config: OpenAiConfig) -> Self {
+        Self { client: Client::new(), config }
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/chat/completions", self.config.base_url.trim_end_matches('/'))
+    }
+}
+
+#[async_trait]
+impl AnalysisBackend for OpenAiBackend {
+    // The model reads any language, so the detected one is not needed
+    async fn analyze(&self, text: &str, _language: Language) -> Result<TextAnalysis, AnalysisError> {
+        let request = ChatRequest {
+            model: &self.config.model,
+            messages: [
+                Message { role: "system", content: SYSTEM_PROMPT },
+                Message { role: "user", content: text },
+            ],
+            temperature: 0.0,
+            response_format: ResponseFormat { kind: "json_object" },
+        };
+
+        let response = self
+            .client
+            .post(self.endpoint())
+            .bearer_auth(&self.config.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| AnalysisError::Transport(Box::new(e)))?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            // Only the delay-seconds form of Retry-After is honoured
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            return Err(AnalysisError::RateLimited { retry_after });
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AnalysisError::Http { status: status.as_u16(), body });
+        }
+
+        let chat: ChatResponse = response.json().await.map_err(|e| AnalysisError::InvalidResponse {
+            reason: "malformed chat completion body".to_string(),
+            source: Some(Box::new(e)),
+        })?;
+
+        let content = chat
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| AnalysisError::InvalidResponse {
+                reason: "response contained no choices".to_string(),
+                source: None,
+            })?;
+
+        parse_model_output(text, &content)
+    }
+
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn identity(&self) -> String {
+        format!("openai:{}@{}:{}", self.config.model, self.config.base_url, digest(&SYSTEM_PROMPT))
+    }
+}
+
+fn parse_model_output(text: &str, content: &str) -> Result<TextAnalysis, AnalysisError> {
+    // Some compatible servers wrap JSON in a markdown code fence despite response_format
+    let json = content
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+
+    let parsed: ModelAnalysis =
+        serde_json::from_str(json).map_err(|e| AnalysisError::InvalidResponse {
+            reason: "model returned an invalid analysis".to_string(),
+            source: Some(Box::new(e)),
+        })?;
+
+    // The model's self-reported confidence is blended with the margin of its scores
+    let confidence = confidence::estimate(&ConfidenceEvidence {
+        sentiment: &parsed.sentiment,
+        total_tokens: tokenize(text).len(),
+        matched_tokens: None,
+        reported: Some(parsed.confidence),
+    });
+
+    let mut key_topics = parsed.key_topics;
+    for topic in &mut key_topics {
+        topic.mentions = find_mentions(text, &topic.name);
+    }
+
+    Ok(TextAnalysis::new(parsed.sentiment, key_topics, confidence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Mention;
+
+    #[test]
+    fn test_parse_model_output() {
+        let content = "```json\n{\"sentiment\":{\"positive\":0.7,\"negative\":0.1,\"neutral\":0.2},\
+            \"key_topics\":[{\"name\":\"pricing\",\"relevance\":0.8,\"category\":\"business\",\
+            \"sentiment\":{\"positive\":0.0,\"negative\":0.9,\"neutral\":0.1}}],\
+            \"confidence\":1.4}\n```";
+        let analysis = parse_model_output("Too expensive, the pricing hurts", content).unwrap();
+        assert_eq!(analysis.key_topics[0].name, "pricing");
+        assert_eq!(analysis.key_topics[0].sentiment.as_ref().unwrap().negative, 0.9);
+        assert_eq!(analysis.key_topics[0].mentions, [Mention { start: 19, end: 26 }]);
+        assert!(analysis.confidence > 0.5 && analysis.confidence <= 1.0);
+
+        assert!(matches!(
+            parse_model_output("Too expensive", "not json"),
+            Err(AnalysisError::InvalidResponse { source: Some(_), .. })
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::AnalysisBackend;
+use crate::analysis::{SentimentScore, TextAnalysis};
+use crate::error::AnalysisError;
+use crate::language::Language;
+
+/// A failure a `ScriptedBackend` can be told to produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Timeout,
+    RateLimited { retry_after: Option<Duration> },
+    Http { status: u16 },
+    Transport,
+    /// A non-retryable `ApiError`.
+    Api,
+}
+
+impl Fault {
+    fn to_error(self, latency: Duration) -> AnalysisError {
+        match self {
+            Fault::Timeout => AnalysisError::Timeout { after: latency },
+            Fault::RateLimited { retry_after } => AnalysisError::RateLimited { retry_after },
+            Fault::Http { status } => AnalysisError::Http { status, body: String::new() },
+            Fault::Transport => AnalysisError::Transport("scripted transport failure".into()),
+            Fault::Api => AnalysisError::ApiError("scripted failure".to_string()),
+        }
+    }
+}
+
+/// What one call to a `ScriptedBackend` does.
+#[derive(Debug, Clone)]
+pub struct ScriptStep {
+    /// How long the call takes, on tokio's clock.
+    pub latency: Duration,
+    pub outcome: StepOutcome,
+}
+
+#[derive(Debug, Clone)]
+pub enum StepOutcome {
+    /// Succeed with this sentiment; confidence is its highest score.
+    Respond(SentimentScore),
+    Fail(Fault),
+}
+
+impl ScriptStep {
+    pub fn respond(sentiment: SentimentScore) -> Self {
+        Self { latency: Duration::ZERO, outcome: StepOutcome::Respond(sentiment) }
+    }
+
+    /// Succeed with a neutral result.
+    pub fn succeed() -> Self {
+        Self::respond(SentimentScore { positive: 0.0, negative: 0.0, neutral: 1.0 })
+    }
+
+    pub fn fail(fault: Fault) -> Self {
+        Self { latency: Duration::ZERO, outcome: StepOutcome::Fail(fault) }
+    }
+
+    pub fn after(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+}
+
+enum Mode {
+    /// Plays the steps in order, then repeats the fallback.
+    Script { steps: VecDeque<ScriptStep>, fallback: ScriptStep },
+    /// Fails with `fault` on a seeded share of calls.
+    FailureRate { rate: f64, fault: Fault, success: ScriptStep, rng: fastrand::Rng },
+}
+
+/// Deterministic fake backend for exercising the analyzer's failure handling:
+/// it replays a script of latencies, errors and responses, or fails a seeded
+/// random share of calls. Latency is slept on tokio's clock, so tests using
+/// `tokio::time::pause` run without waiting.
+pub struct ScriptedBackend {
+    mode: Mutex<Mode>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl ScriptedBackend {
+    /// Plays `steps` in order, one per call, then succeeds instantly.
+    pub fn new(steps: impl IntoIterator<Item = ScriptStep>) -> Self {
+        Self::from_mode(Mode::Script { steps: steps.into_iter().collect(), fallback: ScriptStep::succeed() })
+    }
+
+    /// Fails with `fault` on about `rate` of calls, chosen by a generator
+    /// seeded with `seed` so runs repeat exactly; other calls succeed.
+    pub fn failure_rate(rate: f64, fault: Fault, seed: u64) -> Self {
+        Self::from_mode(Mode::FailureRate {
+            rate: rate.clamp(0.0, 1.0),
+            fault,
+            success: ScriptStep::succeed(),
+            rng: fastrand::Rng::with_seed(seed),
+        })
+    }
+
+    fn from_mode(mode: Mode) -> Self {
+        Self { mode: Mutex::new(mode), calls: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// What calls do once the script has run out, or, in failure-rate mode,
+    /// what the calls that do not fail do.
+    pub fn then(self, step: ScriptStep) -> Self {
+        match &mut *self.mode.lock().unwrap() {
+            Mode::Script { fallback, .. } => *fallback = step,
+            Mode::FailureRate { success, .. } => *success = step,
+        }
+        self
+    }
+
+    /// A counter of the calls made so far, which stays readable after the
+    /// backend is moved into an analyzer.
+    pub fn call_counter(&self) -> Arc<AtomicUsize> {
+        self.calls.clone()
+    }
+
+    fn next_step(&self) -> ScriptStep {
+        match &mut *self.mode.lock().unwrap() {
+            Mode::Script { steps, fallback } => steps.pop_front().unwrap_or_else(|| fallback.clone()),
+            Mode::FailureRate { rate, fault, success, rng } => {
+                if rng.f64() < *rate {
+                    ScriptStep { outcome: StepOutcome::Fail(*fault), ..success.clone() }
+                } else {
+                    success.clone()
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AnalysisBackend for ScriptedBackend {
+    async fn analyze(&self, _text: &str, _language: Language) -> Result<TextAnalysis, AnalysisError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let step = self.next_step();
+        tokio::time::sleep(step.latency).await;
+
+        match step.outcome {
+            StepOutcome::Fail(fault) => Err(fault.to_error(step.latency)),
+            StepOutcome::Respond(sentiment) => {
+                let confidence = sentiment.positive.max(sentiment.negative).max(sentiment.neutral);
+                Ok(TextAnalysis::new(sentiment, Vec::new(), confidence))
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn outcomes(backend: &ScriptedBackend, calls: usize) -> Vec<bool> {
+        let mut outcomes = Vec::new();
+        for _ in 0..calls {
+            outcomes.push(backend.analyze("text", Language::English).await.is_ok());
+        }
+        outcomes
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_script_plays_in_order_then_falls_back() {
+        let backend = ScriptedBackend::new([
+            ScriptStep::fail(Fault::Http { status: 503 }).after(Duration::from_secs(2)),
+            ScriptStep::respond(SentimentScore { positive: 0.8, negative: 0.1, neutral: 0.1 }),
+        ])
+        .then(ScriptStep::fail(Fault::Api));
+
+        let start = tokio::time::Instant::now();
+        assert!(matches!(
+            backend.analyze("text", Language::English).await,
+            Err(AnalysisError::Http { status: 503, .. })
+        ));
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(backend.analyze("text", Language::English).await.unwrap().confidence, 0.8);
+        assert_eq!(outcomes(&backend, 3).await, [false; 3]);
+        assert_eq!(backend.call_counter().load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_failure_rate_is_seeded() {
+        let first = outcomes(&ScriptedBackend::failure_rate(0.3, Fault::Timeout, 7), 200).await;
+        let second = outcomes(&ScriptedBackend::failure_rate(0.3, Fault::Timeout, 7), 200).await;
+        assert_eq!(first, second);
+
+        let failures = first.iter().filter(|ok| !**ok).count();
+        assert!((40..80).contains(&failures), "{} failures", failures);
+        assert_eq!(outcomes(&ScriptedBackend::failure_rate(0.0, Fault::Timeout, 7), 20).await, [true; 20]);
+    }
+}
@@ -0,0 +1,319 @@
+//! Optional LRU cache of analysis results, keyed on the sanitized text and the
+//! backend's identity, with expiry and a file that lets it survive restarts.
+
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::analysis::TextAnalysis;
+use crate::circuit_breaker::{Clock, SystemClock};
+use crate::language::Language;
+
+/// Version of the persisted file layout; files of another version are ignored.
+const FILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Most results kept; the least recently used is evicted beyond this.
+    pub capacity: usize,
+    /// How long a result stays valid; `None` keeps it until evicted.
+    pub ttl: Option<Duration>,
+    /// File read by `AnalysisCache::open` and written by `AnalysisCache::save`.
+    pub path: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            ttl: Some(Duration::from_secs(3600)),
+            path: None,
+        }
+    }
+}
+
+/// Lookups since the cache was created, and how many results it holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+struct Entry {
+    analysis: TextAnalysis,
+    inserted: Instant,
+}
+
+// Read before the rest of the file, which may be laid out differently
+#[derive(Deserialize)]
+struct FileHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    /// Least recently used first.
+    entries: Vec<FileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    key: String,
+    /// Wall-clock insertion time, so entries keep ageing while the process is down.
+    stored_at_ms: u64,
+    analysis: TextAnalysis,
+}
+
+pub struct AnalysisCache {
+    entries: LruCache<String, Entry>,
+    ttl: Option<Duration>,
+    path: Option<PathBuf>,
+    clock: Arc<dyn Clock>,
+    hits: u64,
+    misses: u64,
+}
+
+impl AnalysisCache {
+    /// An empty cache; `config.path` is only used by `save`.
+    pub fn new(config: CacheConfig) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(config: CacheConfig, clock: Arc<dyn Clock>) -> Self {
+        let capacity = NonZeroUsize::new(config.capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: LruCache::new(capacity),
+            ttl: config.ttl,
+            path: config.path,
+            clock,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Creates a cache pre-filled from `config.path` when that file exists.
+    /// Expired entries are skipped, and a file written by another version or
+    /// that cannot be parsed is ignored with a warning, leaving the cache cold.
+    /// Only failing to read the file is an error.
+    pub fn open(config: CacheConfig) -> io::Result<Self> {
+        let mut cache = Self::new(config);
+        if let Some(path) = cache.path.clone() {
+            let bytes = match fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(cache),
+                Err(e) => return Err(e),
+            };
+            match serde_json::from_slice::<FileHeader>(&bytes) {
+                Ok(header) if header.version == FILE_VERSION => match serde_json::from_slice(&bytes) {
+                    Ok(file) => cache.load(file),
+                    Err(e) => tracing::warn!(path = %path.display(), error = %e, "ignoring unreadable cache file"),
+                },
+                Ok(header) => {
+                    tracing::warn!(path = %path.display(), version = header.version, "ignoring cache file of another version")
+                }
+                Err(e) => tracing::warn!(path = %path.display(), error = %e, "ignoring unreadable cache file"),
+            }
+        }
+        Ok(cache)
+    }
+
+    /// The key for `text` in `language` as analyzed by the backend with this `identity`.
+    pub fn key(identity: &str, language: Language, text: &str) -> String {
+        digest(&(identity, language, text))
+    }
+
+    /// A copy of the cached result for `key`, counting a hit or a miss.
+    pub fn get(&mut self, key: &str) -> Option<TextAnalysis> {
+        let now = self.clock.now();
+        let ttl = self.ttl;
+        let cached = match self.entries.get(key) {
+            Some(entry) if !is_expired(ttl, now, entry.inserted) => Some(entry.analysis.clone()),
+            Some(_) => {
+                self.entries.pop(key);
+                None
+            }
+            None => None,
+        };
+
+        match cached {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        cached
+    }
+
+    pub fn insert(&mut self, key: String, analysis: TextAnalysis) {
+        let inserted = self.clock.now();
+        self.entries.put(key, Entry { analysis, inserted });
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { hits: self.hits, misses: self.misses, entries: self.entries.len() }
+    }
+
+    /// Writes unexpired entries to `config.path`, if one was configured.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let now = self.clock.now();
+        let now_ms = unix_millis(SystemTime::now());
+        let entries = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|(_, entry)| !is_expired(self.ttl, now, entry.inserted))
+            .map(|(key, entry)| FileEntry {
+                key: key.clone(),
+                stored_at_ms: now_ms.saturating_sub(now.duration_since(entry.inserted).as_millis() as u64),
+                analysis: entry.analysis.clone(),
+            })
+            .collect();
+
+        // Written beside the target and renamed, so a crash never leaves a truncated file
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec(&CacheFile { version: FILE_VERSION, entries })?)?;
+        fs::rename(&temp, path)
+    }
+
+    fn load(&mut self, file: CacheFile) {
+        let now = self.clock.now();
+        let now_ms = unix_millis(SystemTime::now());
+        for entry in file.entries {
+            let age = Duration::from_millis(now_ms.saturating_sub(entry.stored_at_ms));
+            match now.checked_sub(age) {
+                Some(inserted) if !is_expired(self.ttl, now, inserted) => {
+                    self.entries.put(entry.key, Entry { analysis: entry.analysis, inserted });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_expired(ttl: Option<Duration>, now: Instant, inserted: Instant) -> bool {
+    ttl.is_some_and(|ttl| now.duration_since(inserted) >= ttl)
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// Hex SHA-256 of everything `value` feeds to a `Hasher`. Unlike
+/// `DefaultHasher` it is the same in every process, so it can key persisted data.
+pub(crate) fn digest(value: &impl Hash) -> String {
+    let mut hasher = DigestHasher(Sha256::new());
+    value.hash(&mut hasher);
+    format!("{:x}", hasher.0.finalize())
+}
+
+struct DigestHasher(Sha256);
+
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let hash = self.0.clone().finalize();
+        u64::from_be_bytes(hash[..8].try_into().expect("SHA-256 is 32 bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::SentimentScore;
+    use crate::circuit_breaker::ManualClock;
+    use uuid::Uuid;
+
+    fn analysis(positive: f64) -> TextAnalysis {
+        TextAnalysis {
+            sentiment: SentimentScore { positive, negative: 0.0, neutral: 1.0 - positive },
+            key_topics: Vec::new(),
+            confidence: 0.5,
+            ..TextAnalysis::neutral_for_test()
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction_and_stats() {
+        let mut cache = AnalysisCache::new(CacheConfig { capacity: 2, ..Default::default() });
+        cache.insert("a".to_string(), analysis(0.1));
+        cache.insert("b".to_string(), analysis(0.2));
+        assert!(cache.get("a").is_some());
+        cache.insert("c".to_string(), analysis(0.3));
+
+        // "b" was least recently used
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("c").unwrap().sentiment.positive, 0.3);
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1, entries: 2 });
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let clock = Arc::new(ManualClock::new());
+        let config = CacheConfig { ttl: Some(Duration::from_secs(60)), ..Default::default() };
+        let mut cache = AnalysisCache::with_clock(config, clock.clone());
+        cache.insert("a".to_string(), analysis(0.1));
+
+        clock.advance(Duration::from_secs(59));
+        assert!(cache.get("a").is_some());
+        clock.advance(Duration::from_secs(1));
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_keys_depend_on_backend_identity() {
+        let key = |identity, language, text| AnalysisCache::key(identity, language, text);
+        assert_eq!(key("mock", Language::English, "hello"), key("mock", Language::English, "hello"));
+        assert_ne!(key("mock", Language::English, "hello"), key("lexicon", Language::English, "hello"));
+        assert_ne!(key("mock", Language::English, "hello"), key("mock", Language::German, "hello"));
+        assert_ne!(key("mock", Language::English, "hello"), key("mock", Language::English, "hello!"));
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let path = std::env::temp_dir().join(format!("analysis-cache-{}.json", Uuid::new_v4()));
+        let config = CacheConfig { capacity: 2, path: Some(path.clone()), ..Default::default() };
+
+        let mut cache = AnalysisCache::open(config.clone()).unwrap();
+        cache.insert("a".to_string(), analysis(0.1));
+        cache.insert("b".to_string(), analysis(0.2));
+        cache.save().unwrap();
+
+        let mut reopened = AnalysisCache::open(config).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reopened.stats().entries, 2);
+
+        // Recency survives the round trip: "a" is still the eviction candidate
+        reopened.insert("c".to_string(), analysis(0.3));
+        assert!(reopened.get("a").is_none());
+        assert_eq!(reopened.get("b").unwrap().sentiment.positive, 0.2);
+    }
+
+    #[test]
+    fn test_unreadable_files_leave_the_cache_cold() {
+        let path = std::env::temp_dir().join(format!("analysis-cache-{}.json", Uuid::new_v4()));
+        let config = CacheConfig { path: Some(path.clone()), ..Default::default() };
+
+        // Truncated, and from another version with a different layout
+        for contents in ["{\"version\":1,\"entries\":[{\"key\":", "{\"version\":2,\"entries\":{}}"] {
+            fs::write(&path, contents).unwrap();
+            let cache = AnalysisCache::open(config.clone()).unwrap();
+            assert_eq!(cache.stats().entries, 0);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
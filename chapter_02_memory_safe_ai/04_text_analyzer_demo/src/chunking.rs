@@ -0,0 +1,258 @@
+//! Opt-in analysis of inputs over the size limit: the text is split into
+//! sentence-aligned chunks that each fit the limit, and the chunks' results
+//! are combined into one analysis.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use crate::analysis::{ChunkAnalysis, SentimentScore, TextAnalysis, Topic};
+use crate::language::{Language, LanguageDetection};
+use crate::sanitize::LimitUnit;
+use crate::text::{sentences, tokenize};
+
+#[derive(Debug, Clone)]
+pub struct ChunkingConfig {
+    /// Inputs larger than this many full chunks are still rejected as too large.
+    pub max_chunks: usize,
+    /// Topics kept after merging the chunks' topics.
+    pub max_topics: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self { max_chunks: 64, max_topics: 5 }
+    }
+}
+
+/// A run of whole sentences from the input, with its byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits `text` into chunks of at most `limit` in `unit`, packing as many
+/// consecutive sentences into each as fit. A sentence longer than the limit
+/// is split between words, and a single over-long word between characters.
+pub fn split(text: &str, limit: usize, unit: LimitUnit) -> Vec<Chunk<'_>> {
+    let limit = limit.max(1);
+    let mut pieces = Vec::new();
+    for sentence in sentences(text) {
+        push_fitting(text, sentence.start..sentence.end, limit, unit, &mut pieces);
+    }
+
+    let mut chunks = Vec::new();
+    let mut current: Option<(Range<usize>, usize)> = None;
+    for piece in pieces {
+        current = match current {
+            // The gap between pieces is whitespace, so sizes add up
+            Some((range, size)) => {
+                let added = unit.measure(&text[range.end..piece.end]);
+                if size + added <= limit {
+                    Some((range.start..piece.end, size + added))
+                } else {
+                    chunks.push(chunk(text, range));
+                    Some((piece.clone(), unit.measure(&text[piece])))
+                }
+            }
+            None => Some((piece.clone(), unit.measure(&text[piece]))),
+        };
+    }
+    if let Some((range, _)) = current {
+        chunks.push(chunk(text, range));
+    }
+
+    chunks
+}
+
+fn chunk(text: &str, range: Range<usize>) -> Chunk<'_> {
+    Chunk { text: &text[range.clone()], start: range.start, end: range.end }
+}
+
+// Pushes `range` if it fits, otherwise its words, otherwise its characters
+fn push_fitting(text: &str, range: Range<usize>, limit: usize, unit: LimitUnit, pieces: &mut Vec<Range<usize>>) {
+    let slice = &text[range.clone()];
+    if unit.measure(slice) <= limit {
+        pieces.push(range);
+        return;
+    }
+
+    let words = tokenize(slice);
+    if words.len() > 1 {
+        // Punctuation between words is picked up again when pieces are packed
+        for word in words {
+            push_fitting(text, range.start + word.start..range.start + word.end, limit, unit, pieces);
+        }
+    } else {
+        for (offset, c) in slice.char_indices() {
+            let start = range.start + offset;
+            pieces.push(start..start + c.len_utf8());
+        }
+    }
+}
+
+/// Combines chunk results into one analysis: sentiment, confidence and
+/// topic relevance are averaged weighted by chunk length in characters, and
+/// topics found in several chunks are merged by name. Topic mentions, which
+/// are relative to their chunk, are shifted to offsets into `input`, in the
+/// chunks' own analyses too. The language is the one covering most of the
+/// text. `processing_time` and `request_id` are left for the caller to set.
+pub fn aggregate(input: &str, mut chunks: Vec<ChunkAnalysis>, max_topics: usize) -> TextAnalysis {
+    for c in &mut chunks {
+        for mention in c.analysis.key_topics.iter_mut().flat_map(|topic| &mut topic.mentions) {
+            mention.start += c.start;
+            mention.end += c.start;
+        }
+    }
+
+    let weights: Vec<f64> = chunks.iter().map(|c| input[c.start..c.end].chars().count() as f64).collect();
+    let total = weights.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+    let mean = |value: fn(&TextAnalysis) -> f64| {
+        chunks.iter().zip(&weights).map(|(c, w)| value(&c.analysis) * w).sum::<f64>() / total
+    };
+
+    let sentiment = SentimentScore {
+        positive: mean(|a| a.sentiment.positive),
+        negative: mean(|a| a.sentiment.negative),
+        neutral: mean(|a| a.sentiment.neutral),
+    };
+    let confidence = mean(|a| a.confidence);
+
+    // Keyed case-insensitively; the first chunk's spelling and category win
+    let mut topics: Vec<Topic> = Vec::new();
+    let mut topic_sentiments: Vec<(f64, SentimentScore)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (c, weight) in chunks.iter().zip(&weights) {
+        for topic in &c.analysis.key_topics {
+            let i = *index.entry(topic.name.to_lowercase()).or_insert_with(|| {
+                topics.push(Topic { relevance: 0.0, sentiment: None, mentions: Vec::new(), ..topic.clone() });
+                topic_sentiments.push((0.0, SentimentScore { positive: 0.0, negative: 0.0, neutral: 0.0 }));
+                topics.len() - 1
+            });
+            topics[i].relevance += topic.relevance * weight / total;
+            topics[i].mentions.extend_from_slice(&topic.mentions);
+            if let Some(sentiment) = &topic.sentiment {
+                let (covered, sum) = &mut topic_sentiments[i];
+                *covered += weight;
+                sum.positive += sentiment.positive * weight;
+                sum.negative += sentiment.negative * weight;
+                sum.neutral += sentiment.neutral * weight;
+            }
+        }
+    }
+    for (topic, (covered, sum)) in topics.iter_mut().zip(topic_sentiments) {
+        if covered > 0.0 {
+            topic.sentiment = Some(SentimentScore {
+                positive: sum.positive / covered,
+                negative: sum.negative / covered,
+                neutral: sum.neutral / covered,
+            });
+        }
+    }
+    topics.sort_by(|a, b| b.relevance.total_cmp(&a.relevance).then_with(|| a.name.cmp(&b.name)));
+    topics.truncate(max_topics);
+
+    let mut languages: BTreeMap<Language, (f64, f64)> = BTreeMap::new();
+    for (c, weight) in chunks.iter().zip(&weights) {
+        if let Some(detection) = c.analysis.language {
+            let (covered, confidence) = languages.entry(detection.language).or_default();
+            *covered += weight;
+            *confidence += detection.confidence * weight;
+        }
+    }
+    let mut redactions = BTreeMap::new();
+    for c in &chunks {
+        for (kind, count) in &c.analysis.redactions {
+            *redactions.entry(*kind).or_default() += count;
+        }
+    }
+
+    let language = languages
+        .into_iter()
+        .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+        .map(|(language, (covered, confidence))| LanguageDetection { language, confidence: confidence / covered });
+
+    TextAnalysis {
+        language,
+        attempts: chunks.iter().map(|c| c.analysis.attempts).sum(),
+        redactions,
+        chunks,
+        ..TextAnalysis::new(sentiment, topics, confidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Mention;
+
+    fn analysis(positive: f64, topics: &[(&str, f64)]) -> TextAnalysis {
+        TextAnalysis {
+            sentiment: SentimentScore { positive, negative: 1.0 - positive, neutral: 0.0 },
+            key_topics: topics
+                .iter()
+                .map(|(name, relevance)| Topic {
+                    name: name.to_string(),
+                    relevance: *relevance,
+                    category: "general".to_string(),
+                    sentiment: Some(SentimentScore { positive, negative: 1.0 - positive, neutral: 0.0 }),
+                    mentions: vec![Mention { start: 0, end: 2 }],
+                })
+                .collect(),
+            confidence: positive,
+            language: Some(LanguageDetection { language: Language::English, confidence: 0.5 }),
+            ..TextAnalysis::neutral_for_test()
+        }
+    }
+
+    #[test]
+    fn test_split_keeps_sentences_whole() {
+        let text = "First one here. Second one here. Third is a little longer.";
+        let chunks = split(text, 35, LimitUnit::Chars);
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text).collect();
+        assert_eq!(texts, ["First one here. Second one here.", "Third is a little longer."]);
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_split_breaks_long_sentences_and_words() {
+        let text = "one two three four five six seven";
+        let chunks = split(text, 3, LimitUnit::Tokens);
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text).collect();
+        assert_eq!(texts, ["one two three", "four five six", "seven"]);
+
+        let word = "ä".repeat(5);
+        let chunks = split(&word, 4, LimitUnit::Bytes);
+        assert!(chunks.iter().all(|c| c.text.len() <= 4));
+        assert_eq!(chunks.iter().map(|c| c.text).collect::<String>(), word);
+    }
+
+    #[test]
+    fn test_aggregate_weights_by_length() {
+        let input = format!("{}{}", "a".repeat(30), "b".repeat(10));
+        let chunks = vec![
+            ChunkAnalysis { start: 0, end: 30, analysis: analysis(1.0, &[("Pricing", 0.5), ("support", 0.2)]) },
+            ChunkAnalysis { start: 30, end: 40, analysis: analysis(0.0, &[("pricing", 1.0)]) },
+        ];
+        let combined = aggregate(&input, chunks, 5);
+
+        assert!((combined.sentiment.positive - 0.75).abs() < 1e-9);
+        assert!((combined.sentiment.negative - 0.25).abs() < 1e-9);
+        let topics: Vec<(&str, f64)> = combined.key_topics.iter().map(|t| (t.name.as_str(), t.relevance)).collect();
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].0, "Pricing");
+        assert!((topics[0].1 - 0.625).abs() < 1e-9);
+        assert!((topics[1].1 - 0.15).abs() < 1e-9);
+        // "Pricing" was praised in 30 characters and criticized in 10
+        assert!((combined.key_topics[0].sentiment.as_ref().unwrap().positive - 0.75).abs() < 1e-9);
+        assert_eq!(combined.language.unwrap().language, Language::English);
+        assert_eq!(combined.chunks.len(), 2);
+
+        // Mentions end up as offsets into the input, merged per topic
+        assert_eq!(combined.key_topics[0].mentions, [Mention { start: 0, end: 2 }, Mention { start: 30, end: 32 }]);
+        assert_eq!(combined.chunks[1].analysis.key_topics[0].mentions, [Mention { start: 30, end: 32 }]);
+    }
+}
@@ -0,0 +1,385 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of the current time for the breaker, so cooldowns can be tested
+/// without sleeping.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The real monotonic clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Tokio's clock, which follows `tokio::time::pause` and `advance`, so the
+/// breaker's cooldown can be driven by the same virtual time as the calls.
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+}
+
+/// A clock that only moves when told to.
+pub struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self { now: Mutex::new(Instant::now()) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Calls flow normally; consecutive failures are being counted.
+    Closed,
+    /// Calls are rejected until the cooldown has elapsed.
+    Open,
+    /// A limited number of probe calls are let through to test recovery.
+    HalfOpen,
+}
+
+impl BreakerState {
+    /// The state's snake_case name, as serialized.
+    pub fn name(self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that trip the breaker from closed to open.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before allowing probes.
+    pub cooldown: Duration,
+    /// Probe calls admitted while half-open; this many must succeed to close.
+    /// At least one probe is always admitted, so 0 acts as 1.
+    pub half_open_max_calls: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+            half_open_max_calls: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed { failures: u32 },
+    Open { since: Instant },
+    // `generation` tells this half-open period's probes from earlier ones
+    HalfOpen { generation: u64, admitted: u32, succeeded: u32 },
+}
+
+/// An admitted call, handed back to the breaker with the call's outcome.
+/// It remembers whether the call was a probe, and for which half-open
+/// period, so calls that outlive the state that admitted them are not
+/// mistaken for the current probes.
+#[derive(Debug)]
+#[must_use = "the call's outcome must be recorded with this permit"]
+pub struct Permit {
+    probe: Option<u64>,
+}
+
+// Circuit breaker for safety
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: State,
+    // Half-open periods entered so far
+    generation: u64,
+    clock: Arc<dyn Clock>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self::with_config(CircuitBreakerConfig::default())
+    }
+
+    pub fn with_config(config: CircuitBreakerConfig) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    /// A breaker that never opens, for runs such as evaluations where every
+    /// call has to reach the backend however many fail.
+    pub fn disabled() -> Self {
+        Self::with_config(CircuitBreakerConfig { failure_threshold: u32::MAX, ..Default::default() })
+    }
+
+    pub fn with_clock(mut config: CircuitBreakerConfig, clock: Arc<dyn Clock>) -> Self {
+        // With no probes admitted the breaker could never leave half-open
+        config.half_open_max_calls = config.half_open_max_calls.max(1);
+        Self {
+            config,
+            state: State::Closed { failures: 0 },
+            generation: 0,
+            clock,
+        }
+    }
+
+    /// Current state, treating an open breaker whose cooldown has elapsed as half-open.
+    pub fn state(&self) -> BreakerState {
+        match self.state {
+            State::Closed { .. } => BreakerState::Closed,
+            State::Open { since } if self.cooldown_elapsed(since) => BreakerState::HalfOpen,
+            State::Open { .. } => BreakerState::Open,
+            State::HalfOpen { .. } => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether a call would currently be admitted. Does not consume a probe.
+    pub fn can_execute(&self) -> bool {
+        match self.state {
+            State::Closed { .. } => true,
+            State::Open { since } => self.cooldown_elapsed(since),
+            State::HalfOpen { admitted, .. } => admitted < self.config.half_open_max_calls,
+        }
+    }
+
+    /// Admits a call if the breaker allows it, consuming a half-open probe.
+    pub fn try_acquire(&mut self) -> Option<Permit> {
+        if let State::Open { since } = self.state {
+            if !self.cooldown_elapsed(since) {
+                return None;
+            }
+            self.generation += 1;
+            self.state = State::HalfOpen { generation: self.generation, admitted: 0, succeeded: 0 };
+        }
+
+        match &mut self.state {
+            State::Closed { .. } => Some(Permit { probe: None }),
+            State::HalfOpen { generation, admitted, .. } if *admitted < self.config.half_open_max_calls => {
+                *admitted += 1;
+                Some(Permit { probe: Some(*generation) })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn record_failure(&mut self, permit: Permit) {
+        match &mut self.state {
+            State::Closed { failures } => {
+                *failures = failures.saturating_add(1);
+                if *failures >= self.config.failure_threshold {
+                    self.trip();
+                }
+            }
+            State::HalfOpen { generation, .. } if permit.probe == Some(*generation) => self.trip(),
+            // Late results from calls admitted before the breaker opened, or
+            // by an earlier half-open period
+            State::HalfOpen { .. } | State::Open { .. } => {}
+        }
+    }
+
+    pub fn record_success(&mut self, permit: Permit) {
+        match &mut self.state {
+            State::Closed { failures } => *failures = 0,
+            State::HalfOpen { generation, succeeded, .. } if permit.probe == Some(*generation) => {
+                *succeeded += 1;
+                if *succeeded >= self.config.half_open_max_calls {
+                    self.state = State::Closed { failures: 0 };
+                }
+            }
+            State::HalfOpen { .. } | State::Open { .. } => {}
+        }
+    }
+
+    /// Gives back the half-open probe slot held by a call that was abandoned
+    /// before it finished, e.g. because its caller went away. The call counts
+    /// as neither a success nor a failure.
+    pub fn release(&mut self, permit: Permit) {
+        match &mut self.state {
+            State::HalfOpen { generation, admitted, .. } if permit.probe == Some(*generation) => {
+                *admitted = admitted.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    fn trip(&mut self) {
+        self.state = State::Open { since: self.clock.now() };
+    }
+
+    fn cooldown_elapsed(&self, since: Instant) -> bool {
+        self.clock.now().duration_since(since) >= self.config.cooldown
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker_with_clock(half_open_max_calls: u32) -> (CircuitBreaker, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new());
+        let config = CircuitBreakerConfig {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(10),
+            half_open_max_calls,
+        };
+        (CircuitBreaker::with_clock(config, clock.clone()), clock)
+    }
+
+    fn fail(breaker: &mut CircuitBreaker, times: usize) {
+        for _ in 0..times {
+            let permit = breaker.try_acquire().unwrap();
+            breaker.record_failure(permit);
+        }
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let (mut breaker, clock) = breaker_with_clock(1);
+        assert!(breaker.can_execute());
+
+        // Simulate failures
+        fail(&mut breaker, 3);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.try_acquire().is_none());
+
+        // Cooldown elapses and a single probe is admitted
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_none());
+
+        // Recovery
+        breaker.record_success(probe);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.can_execute());
+    }
+
+    #[test]
+    fn test_half_open_failure_reopens() {
+        let (mut breaker, clock) = breaker_with_clock(2);
+        fail(&mut breaker, 3);
+        clock.advance(Duration::from_secs(10));
+
+        let first = breaker.try_acquire().unwrap();
+        let second = breaker.try_acquire().unwrap();
+        breaker.record_success(first);
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        breaker.record_failure(second);
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // The cooldown restarts from the probe failure
+        clock.advance(Duration::from_secs(9));
+        assert!(!breaker.can_execute());
+        clock.advance(Duration::from_secs(1));
+        assert!(breaker.can_execute());
+    }
+
+    #[test]
+    fn test_released_probe_can_be_retaken() {
+        let (mut breaker, clock) = breaker_with_clock(1);
+        fail(&mut breaker, 3);
+        clock.advance(Duration::from_secs(10));
+
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_none());
+        breaker.release(probe);
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        let probe = breaker.try_acquire().unwrap();
+
+        // Releasing outside half-open changes nothing
+        breaker.record_success(probe);
+        let call = breaker.try_acquire().unwrap();
+        breaker.release(call);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_calls_from_before_half_open_are_not_probes() {
+        let (mut breaker, clock) = breaker_with_clock(1);
+        let stale = [(); 3].map(|_| breaker.try_acquire().unwrap());
+        fail(&mut breaker, 3);
+        clock.advance(Duration::from_secs(10));
+        let probe = breaker.try_acquire().unwrap();
+
+        // Admitted while closed, so they hold no probe slot and prove nothing
+        let [dropped, succeeded, failed] = stale;
+        breaker.release(dropped);
+        assert!(breaker.try_acquire().is_none());
+        breaker.record_success(succeeded);
+        breaker.record_failure(failed);
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+
+        breaker.record_success(probe);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        // Nor do probes from an earlier half-open period
+        let (mut breaker, clock) = breaker_with_clock(2);
+        fail(&mut breaker, 3);
+        clock.advance(Duration::from_secs(10));
+        let old_probe = breaker.try_acquire().unwrap();
+        fail(&mut breaker, 1);
+        clock.advance(Duration::from_secs(10));
+        let probes = [(); 2].map(|_| breaker.try_acquire().unwrap());
+        breaker.release(old_probe);
+        assert!(breaker.try_acquire().is_none());
+        for probe in probes {
+            breaker.record_success(probe);
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_zero_probes_still_admits_one() {
+        let (mut breaker, clock) = breaker_with_clock(0);
+        fail(&mut breaker, 3);
+        clock.advance(Duration::from_secs(10));
+
+        let probe = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_none());
+        breaker.record_success(probe);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let (mut breaker, _clock) = breaker_with_clock(1);
+        fail(&mut breaker, 2);
+        let permit = breaker.try_acquire().unwrap();
+        breaker.record_success(permit);
+        fail(&mut breaker, 1);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
@@ -0,0 +1,112 @@
+//! Confidence estimation from the evidence behind an analysis.
+//!
+//! The estimate is a logistic function of three features, each in [0, 1]:
+//!
+//! * **margin** – how far the winning sentiment bucket is ahead of the runner-up;
+//! * **coverage** – `1 - e^(-matched / 2)` over sentiment-bearing tokens, so one
+//!   match gives 0.39, two give 0.63 and three give 0.78;
+//! * **length** – token count relative to [`FULL_LENGTH_TOKENS`], capped at 1.
+//!
+//! `z = -1.5 + 3.0·margin + 1.2·coverage + 0.8·length` and the lexical estimate
+//! is `1 / (1 + e^-z)`. Backends that cannot count tokens (remote models) drop
+//! the coverage term. When a backend reports its own probability the result is
+//! the mean of the two.
+//!
+//! Calibration: the weights are chosen so the lexical estimate spans roughly
+//! 0.18 (no evidence) to 0.97 (decisive, long, well-covered text). Treat values
+//! below 0.4 as weak, 0.4–0.7 as moderate and above 0.7 as strong evidence.
+
+use crate::analysis::SentimentScore;
+
+/// Token count at which input length stops adding confidence.
+pub const FULL_LENGTH_TOKENS: usize = 8;
+
+const BIAS: f64 = -1.5;
+const MARGIN_WEIGHT: f64 = 3.0;
+const COVERAGE_WEIGHT: f64 = 1.2;
+const LENGTH_WEIGHT: f64 = 0.8;
+/// Matched tokens at which coverage reaches `1 - 1/e`.
+const COVERAGE_SCALE: f64 = 2.0;
+
+/// What a backend knows about how well-supported its analysis is.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfidenceEvidence<'a> {
+    pub sentiment: &'a SentimentScore,
+    pub total_tokens: usize,
+    /// Sentiment-bearing tokens, when the backend can count them.
+    pub matched_tokens: Option<usize>,
+    /// The backend's own probability for its answer, if it reports one.
+    pub reported: Option<f64>,
+}
+
+/// Confidence in [0, 1] for an analysis, per the calibration above.
+pub fn estimate(evidence: &ConfidenceEvidence) -> f64 {
+    let mut z = BIAS
+        + MARGIN_WEIGHT * margin(evidence.sentiment)
+        + LENGTH_WEIGHT * (evidence.total_tokens as f64 / FULL_LENGTH_TOKENS as f64).min(1.0);
+    if let Some(matched) = evidence.matched_tokens {
+        z += COVERAGE_WEIGHT * (1.0 - (-(matched as f64) / COVERAGE_SCALE).exp());
+    }
+    let lexical = 1.0 / (1.0 + (-z).exp());
+
+    match evidence.reported {
+        Some(reported) if reported.is_finite() => (lexical + reported.clamp(0.0, 1.0)) / 2.0,
+        _ => lexical,
+    }
+}
+
+/// Gap between the highest and second-highest sentiment bucket.
+pub fn margin(sentiment: &SentimentScore) -> f64 {
+    let mut scores = [sentiment.positive, sentiment.negative, sentiment.neutral];
+    scores.sort_by(|a, b| b.total_cmp(a));
+    (scores[0] - scores[1]).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(sentiment: &SentimentScore, tokens: usize, matched: usize) -> ConfidenceEvidence<'_> {
+        ConfidenceEvidence { sentiment, total_tokens: tokens, matched_tokens: Some(matched), reported: None }
+    }
+
+    #[test]
+    fn test_calibration_bounds() {
+        let flat = SentimentScore { positive: 0.4, negative: 0.4, neutral: 0.2 };
+        let decisive = SentimentScore { positive: 1.0, negative: 0.0, neutral: 0.0 };
+
+        let none = estimate(&evidence(&flat, 0, 0));
+        let all = estimate(&evidence(&decisive, 20, 20));
+        assert!((none - 0.18).abs() < 0.01, "{}", none);
+        assert!((all - 0.97).abs() < 0.01, "{}", all);
+    }
+
+    #[test]
+    fn test_more_evidence_raises_confidence() {
+        let sentiment = SentimentScore { positive: 0.6, negative: 0.1, neutral: 0.3 };
+        let one = estimate(&evidence(&sentiment, 4, 1));
+        assert!(estimate(&evidence(&sentiment, 4, 3)) > one);
+        assert!(estimate(&evidence(&sentiment, 12, 1)) > one);
+
+        let wider = SentimentScore { positive: 0.8, negative: 0.1, neutral: 0.1 };
+        assert!(estimate(&evidence(&wider, 4, 1)) > one);
+    }
+
+    #[test]
+    fn test_reported_probability_is_blended() {
+        let sentiment = SentimentScore { positive: 0.5, negative: 0.2, neutral: 0.3 };
+        let lexical = estimate(&ConfidenceEvidence {
+            sentiment: &sentiment,
+            total_tokens: 8,
+            matched_tokens: None,
+            reported: None,
+        });
+        let blended = estimate(&ConfidenceEvidence {
+            sentiment: &sentiment,
+            total_tokens: 8,
+            matched_tokens: None,
+            reported: Some(1.0),
+        });
+        assert!((blended - (lexical + 1.0) / 2.0).abs() < 1e-12);
+    }
+}
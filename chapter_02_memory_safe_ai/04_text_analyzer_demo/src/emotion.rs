@@ -0,0 +1,274 @@
+//! Offline, lexicon-based emotion scoring in the style of the NRC Emotion
+//! Lexicon: every word associated with an emotion counts towards it, and
+//! the scores are each emotion's share of those associations.
+//!
+//! Words within three words after a negation in the lexicon's language are
+//! skipped, since "not happy" says little about which emotion is meant.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::language::Language;
+use crate::sentiment;
+use crate::text::tokenize;
+
+const ENGLISH_EMOTIONS: &str = include_str!("../data/emotions_en.tsv");
+
+/// How many preceding words a negation reaches.
+const NEGATION_WINDOW: usize = 3;
+
+/// The emotions scored, after Plutchik's basic emotions as used by NRC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Emotion {
+    Anger,
+    Joy,
+    Sadness,
+    Fear,
+    Surprise,
+    Trust,
+}
+
+impl Emotion {
+    pub const ALL: [Emotion; 6] =
+        [Emotion::Anger, Emotion::Joy, Emotion::Sadness, Emotion::Fear, Emotion::Surprise, Emotion::Trust];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Emotion::Anger => "anger",
+            Emotion::Joy => "joy",
+            Emotion::Sadness => "sadness",
+            Emotion::Fear => "fear",
+            Emotion::Surprise => "surprise",
+            Emotion::Trust => "trust",
+        }
+    }
+}
+
+impl fmt::Display for Emotion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Emotion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Emotion::ALL
+            .into_iter()
+            .find(|emotion| emotion.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown emotion `{}`", s.trim()))
+    }
+}
+
+/// Each emotion's share of the emotion words found in a text; all zero when
+/// none were found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmotionScores {
+    pub anger: f64,
+    pub joy: f64,
+    pub sadness: f64,
+    pub fear: f64,
+    pub surprise: f64,
+    pub trust: f64,
+}
+
+impl EmotionScores {
+    pub fn get(&self, emotion: Emotion) -> f64 {
+        match emotion {
+            Emotion::Anger => self.anger,
+            Emotion::Joy => self.joy,
+            Emotion::Sadness => self.sadness,
+            Emotion::Fear => self.fear,
+            Emotion::Surprise => self.surprise,
+            Emotion::Trust => self.trust,
+        }
+    }
+
+    fn get_mut(&mut self, emotion: Emotion) -> &mut f64 {
+        match emotion {
+            Emotion::Anger => &mut self.anger,
+            Emotion::Joy => &mut self.joy,
+            Emotion::Sadness => &mut self.sadness,
+            Emotion::Fear => &mut self.fear,
+            Emotion::Surprise => &mut self.surprise,
+            Emotion::Trust => &mut self.trust,
+        }
+    }
+
+    /// The highest-scoring emotion; ties go to the one earlier in
+    /// `Emotion::ALL`, and there is none when no emotion words were found.
+    pub fn dominant(&self) -> Option<Emotion> {
+        Emotion::ALL
+            .into_iter()
+            .filter(|emotion| self.get(*emotion) > 0.0)
+            .fold(None, |best: Option<Emotion>, emotion| match best {
+                Some(best) if self.get(best) >= self.get(emotion) => Some(best),
+                _ => Some(emotion),
+            })
+    }
+}
+
+/// Words mapped to the emotions they are associated with, plus the negation
+/// words that cancel them.
+#[derive(Debug, Clone)]
+pub struct EmotionLexicon {
+    words: HashMap<String, Vec<Emotion>>,
+    negations: HashSet<String>,
+    language: Language,
+}
+
+impl EmotionLexicon {
+    /// The bundled English emotion lexicon.
+    pub fn english() -> Self {
+        Self::parse(ENGLISH_EMOTIONS).expect("bundled emotion lexicon is valid")
+    }
+
+    /// Loads an emotion lexicon file of `word<TAB>emotion` lines.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses emotion lexicon lines; blank lines and `#` comments are skipped
+    /// and a word may appear on several lines. The lexicon is taken to be
+    /// English, with English negations, unless `with_language` says otherwise.
+    pub fn parse(source: &str) -> io::Result<Self> {
+        let mut words: HashMap<String, Vec<Emotion>> = HashMap::new();
+        for (number, line) in source.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (word, emotion) = line
+                .split_once('\t')
+                .and_then(|(word, emotion)| Some((word.trim().to_lowercase(), emotion.parse::<Emotion>().ok()?)))
+                .filter(|(word, _)| !word.is_empty())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("emotion lexicon line {}: expected `word<TAB>emotion`", number + 1),
+                    )
+                })?;
+            let emotions = words.entry(word).or_default();
+            if !emotions.contains(&emotion) {
+                emotions.push(emotion);
+            }
+        }
+        let lexicon = Self { words, negations: HashSet::new(), language: Language::English };
+        Ok(lexicon.with_language(Language::English))
+    }
+
+    /// The language of the lexicon's words; text in other languages is not
+    /// scored. Also switches to the bundled negations for `language`, which
+    /// are empty for French and Portuguese until set with `with_negations`.
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self.negations = sentiment::bundled_negations(language).iter().map(|w| w.to_string()).collect();
+        self
+    }
+
+    pub fn with_negations<I: IntoIterator<Item = String>>(mut self, negations: I) -> Self {
+        self.negations = negations.into_iter().map(|w| w.to_lowercase()).collect();
+        self
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn emotions(&self, word: &str) -> &[Emotion] {
+        self.words.get(word).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn score(&self, text: &str) -> EmotionScores {
+        let words: Vec<String> = tokenize(text).iter().map(|t| t.lowercase()).collect();
+        let english = self.language == Language::English;
+        let is_negation = |word: &str| {
+            self.negations.contains(word) || (english && (word.ends_with("n't") || word.ends_with("n\u{2019}t")))
+        };
+
+        let mut scores = EmotionScores::default();
+        let mut total = 0.0;
+        for (i, word) in words.iter().enumerate() {
+            let emotions = self.emotions(word);
+            if emotions.is_empty() || (1..=NEGATION_WINDOW.min(i)).any(|distance| is_negation(&words[i - distance])) {
+                continue;
+            }
+            for emotion in emotions {
+                *scores.get_mut(*emotion) += 1.0;
+                total += 1.0;
+            }
+        }
+
+        if total > 0.0 {
+            for emotion in Emotion::ALL {
+                *scores.get_mut(emotion) /= total;
+            }
+        }
+        scores
+    }
+}
+
+impl Default for EmotionLexicon {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_are_shares_of_emotion_words() {
+        let lexicon = EmotionLexicon::english();
+        let scores = lexicon.score("I am FURIOUS, this is a scam and support was rude.");
+        assert_eq!(scores.dominant(), Some(Emotion::Anger));
+        let total: f64 = Emotion::ALL.iter().map(|e| scores.get(*e)).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        assert_eq!(lexicon.score("Delighted with it, thank you!").dominant(), Some(Emotion::Joy));
+        assert_eq!(lexicon.score("I'm confused, the invoice was unexpected").dominant(), Some(Emotion::Surprise));
+        assert_eq!(lexicon.score("The parcel arrived on Tuesday"), EmotionScores::default());
+        assert_eq!(EmotionScores::default().dominant(), None);
+    }
+
+    #[test]
+    fn test_negated_words_are_skipped() {
+        let lexicon = EmotionLexicon::english();
+        assert_eq!(lexicon.score("I am not happy").joy, 0.0);
+        assert_eq!(lexicon.score("I wasn't really worried, just sad").dominant(), Some(Emotion::Sadness));
+    }
+
+    #[test]
+    fn test_parse() {
+        let lexicon = EmotionLexicon::parse("# comment\nGrim\tsadness\ngrim\tfear\ngrim\tfear\n").unwrap();
+        assert_eq!(lexicon.emotions("grim"), [Emotion::Sadness, Emotion::Fear]);
+        assert_eq!(lexicon.score("grim").sadness, 0.5);
+
+        // Negations follow the lexicon's language
+        let spanish = EmotionLexicon::parse("feliz\tjoy\nhappy\tjoy\n").unwrap().with_language(Language::Spanish);
+        assert_eq!(spanish.score("no estoy feliz"), EmotionScores::default());
+        assert_eq!(spanish.score("not happy").joy, 1.0);
+        let french = EmotionLexicon::parse("heureux\tjoy\n").unwrap().with_language(Language::French);
+        let french = french.with_negations(["pas".to_string()]);
+        assert_eq!(french.score("pas heureux"), EmotionScores::default());
+
+        let error = EmotionLexicon::parse("glad\tjoy\nbored\tboredom\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("emotion lexicon line 2:"));
+    }
+}
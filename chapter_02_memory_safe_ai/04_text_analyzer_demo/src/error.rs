@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use crate::language::Language;
+use crate::sanitize::LimitUnit;
+
+/// Boxed underlying cause carried by some `AnalysisError` variants.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+// Error handling - explicit and safe
+#[derive(Debug)]
+pub enum AnalysisError {
+    EmptyInput,
+    /// Input exceeded the configured size limit; both sizes are in `unit`.
+    InputTooLarge { limit: usize, actual: usize, unit: LimitUnit },
+    /// The input is in a language the backend cannot score.
+    UnsupportedLanguage { language: Language },
+    /// A backend failure described only by a message.
+    ApiError(String),
+    /// The backend answered with a non-success HTTP status.
+    Http { status: u16, body: String },
+    /// The backend asked us to slow down, optionally saying for how long.
+    RateLimited { retry_after: Option<Duration> },
+    /// A single backend call did not answer in time.
+    Timeout { after: Duration },
+    /// The whole analysis, retries included, ran past its deadline.
+    DeadlineExceeded { after: Duration },
+    /// The request never got a response, e.g. connection refused or reset.
+    Transport(BoxError),
+    /// The backend answered but the payload could not be understood.
+    InvalidResponse { reason: String, source: Option<BoxError> },
+    CircuitBreakerOpen,
+}
+
+impl AnalysisError {
+    /// Whether the same request may succeed if tried again later.
+    ///
+    /// Transient conditions (timeouts, transport failures, rate limits, 5xx,
+    /// 408) are retryable; bad input, malformed responses, client errors and
+    /// an open circuit breaker are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AnalysisError::Timeout { .. }
+            | AnalysisError::Transport(_)
+            | AnalysisError::RateLimited { .. } => true,
+            AnalysisError::Http { status, .. } => *status >= 500 || *status == 408,
+            AnalysisError::EmptyInput
+            | AnalysisError::InputTooLarge { .. }
+            | AnalysisError::UnsupportedLanguage { .. }
+            | AnalysisError::ApiError(_)
+            | AnalysisError::InvalidResponse { .. }
+            | AnalysisError::DeadlineExceeded { .. }
+            | AnalysisError::CircuitBreakerOpen => false,
+        }
+    }
+
+    /// Stable snake_case name of the variant, for logs, metrics and API bodies.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AnalysisError::EmptyInput => "empty_input",
+            AnalysisError::InputTooLarge { .. } => "input_too_large",
+            AnalysisError::UnsupportedLanguage { .. } => "unsupported_language",
+            AnalysisError::ApiError(_) => "api_error",
+            AnalysisError::Http { .. } => "http",
+            AnalysisError::RateLimited { .. } => "rate_limited",
+            AnalysisError::Timeout { .. } => "timeout",
+            AnalysisError::DeadlineExceeded { .. } => "deadline_exceeded",
+            AnalysisError::Transport(_) => "transport",
+            AnalysisError::InvalidResponse { .. } => "invalid_response",
+            AnalysisError::CircuitBreakerOpen => "circuit_breaker_open",
+        }
+    }
+
+    /// How long the backend asked callers to wait before retrying, if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AnalysisError::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AnalysisError::EmptyInput => write!(f, "Input text is empty"),
+            AnalysisError::InputTooLarge { limit, actual, unit } => {
+                write!(f, "Input text too large: {} {} exceeds the limit of {}", actual, unit, limit)
+            }
+            AnalysisError::UnsupportedLanguage { language } => {
+                write!(f, "Unsupported language: {}", language)
+            }
+            AnalysisError::ApiError(msg) => write!(f, "API error: {}", msg),
+            AnalysisError::Http { status, body } if body.is_empty() => {
+                write!(f, "Backend returned HTTP {}", status)
+            }
+            AnalysisError::Http { status, body } => {
+                write!(f, "Backend returned HTTP {}: {}", status, body)
+            }
+            AnalysisError::RateLimited { retry_after: Some(wait) } => {
+                write!(f, "Rate limited, retry after {:?}", wait)
+            }
+            AnalysisError::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            AnalysisError::Timeout { after } => write!(f, "Backend timed out after {:?}", after),
+            AnalysisError::DeadlineExceeded { after } => {
+                write!(f, "Analysis did not finish within its {:?} deadline", after)
+            }
+            AnalysisError::Transport(_) => write!(f, "Backend request failed"),
+            AnalysisError::InvalidResponse { reason, .. } => {
+                write!(f, "Invalid backend response: {}", reason)
+            }
+            AnalysisError::CircuitBreakerOpen => write!(f, "Circuit breaker open"),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnalysisError::Transport(source)
+            | AnalysisError::InvalidResponse { source: Some(source), .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_retryable_classification() {
+        assert!(AnalysisError::Http { status: 503, body: String::new() }.is_retryable());
+        assert!(AnalysisError::Http { status: 408, body: String::new() }.is_retryable());
+        assert!(!AnalysisError::Http { status: 401, body: String::new() }.is_retryable());
+        assert!(AnalysisError::Timeout { after: Duration::from_secs(1) }.is_retryable());
+        assert!(!AnalysisError::InputTooLarge { limit: 10, actual: 11, unit: LimitUnit::Bytes }.is_retryable());
+        assert!(!AnalysisError::CircuitBreakerOpen.is_retryable());
+        assert!(!AnalysisError::DeadlineExceeded { after: Duration::from_secs(1) }.is_retryable());
+
+        let limited = AnalysisError::RateLimited { retry_after: Some(Duration::from_secs(2)) };
+        assert!(limited.is_retryable());
+        assert_eq!(limited.retry_after(), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_source_chain() {
+        let cause = serde_json::from_str::<u32>("nope").unwrap_err();
+        let err = AnalysisError::InvalidResponse {
+            reason: "bad analysis".to_string(),
+            source: Some(Box::new(cause)),
+        };
+        assert_eq!(err.to_string(), "Invalid backend response: bad analysis");
+        assert!(err.source().unwrap().downcast_ref::<serde_json::Error>().is_some());
+
+        assert!(AnalysisError::EmptyInput.source().is_none());
+    }
+}
@@ -0,0 +1,538 @@
+//! Measures an analyzer against a labeled dataset: accuracy, per-class
+//! precision, recall and F1, a confusion matrix and the calibration of
+//! `confidence`, optionally compared with a saved baseline.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::analysis::SentimentScore;
+use crate::analyzer::TextAnalyzer;
+
+/// Width of the confidence bins calibration is measured over.
+const CALIBRATION_BIN_WIDTH: f64 = 0.1;
+
+/// The sentiment class a text is labeled with or predicted as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Label {
+    Positive,
+    Negative,
+    Neutral,
+}
+
+impl Label {
+    pub const ALL: [Label; 3] = [Label::Positive, Label::Negative, Label::Neutral];
+
+    /// The class with the highest score; ties count as neutral.
+    pub fn of(sentiment: &SentimentScore) -> Self {
+        let SentimentScore { positive, negative, neutral } = *sentiment;
+        if positive > negative && positive > neutral {
+            Label::Positive
+        } else if negative > positive && negative > neutral {
+            Label::Negative
+        } else {
+            Label::Neutral
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Label::Positive => "positive",
+            Label::Negative => "negative",
+            Label::Neutral => "neutral",
+        }
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Label {
+    type Err = String;
+
+    /// Accepts the full names and `pos`, `neg` and `neu`, in any case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "positive" | "pos" => Ok(Label::Positive),
+            "negative" | "neg" => Ok(Label::Negative),
+            "neutral" | "neu" => Ok(Label::Neutral),
+            other => Err(format!("unknown label `{}`, expected positive, negative or neutral", other)),
+        }
+    }
+}
+
+/// One text of a dataset with its expected label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabeledExample {
+    pub id: String,
+    pub text: String,
+    pub label: Label,
+}
+
+#[derive(Deserialize)]
+struct RawExample {
+    #[serde(default)]
+    id: Option<String>,
+    text: String,
+    label: String,
+}
+
+/// Loads a dataset from a `.csv` file with `text` and `label` columns (and
+/// optionally `id`), or otherwise from JSON Lines of the same fields. IDs
+/// default to the 1-based line number.
+pub fn load_dataset(path: impl AsRef<Path>) -> io::Result<Vec<LabeledExample>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)?;
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv")) {
+        parse_csv(file)
+    } else {
+        let mut source = String::new();
+        io::BufReader::new(file).read_to_string(&mut source)?;
+        parse_jsonl(&source)
+    }
+}
+
+/// Parses JSON Lines examples; blank lines are skipped.
+pub fn parse_jsonl(source: &str) -> io::Result<Vec<LabeledExample>> {
+    let mut examples = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let raw: RawExample = serde_json::from_str(line).map_err(|e| invalid(index + 1, e))?;
+        examples.push(labeled(raw, index + 1)?);
+    }
+    Ok(examples)
+}
+
+/// Parses CSV examples with a header row naming the columns.
+pub fn parse_csv(source: impl Read) -> io::Result<Vec<LabeledExample>> {
+    let mut reader = csv::Reader::from_reader(source);
+    let headers = reader.headers()?.clone();
+    let mut examples = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        let raw: RawExample = record.deserialize(Some(&headers)).map_err(|e| invalid(line, e))?;
+        examples.push(labeled(raw, line)?);
+    }
+    Ok(examples)
+}
+
+fn labeled(raw: RawExample, line: usize) -> io::Result<LabeledExample> {
+    Ok(LabeledExample {
+        id: raw.id.unwrap_or_else(|| line.to_string()),
+        label: raw.label.parse().map_err(|e| invalid(line, e))?,
+        text: raw.text,
+    })
+}
+
+fn invalid(line: usize, error: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, error))
+}
+
+/// What the analyzer predicted for one example.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub label: Label,
+    pub confidence: f64,
+}
+
+/// Scores of an analyzer over a dataset. Examples the analyzer failed on are
+/// counted in `failed` and as wrong predictions of their class; having no
+/// confidence, they are left out of the calibration only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Evaluation {
+    /// Name of the backend that was evaluated.
+    pub backend: String,
+    pub examples: usize,
+    pub failed: usize,
+    pub accuracy: f64,
+    /// Unweighted mean of the F1 scores of the classes that were expected
+    /// or predicted at least once.
+    pub macro_f1: f64,
+    pub classes: BTreeMap<Label, ClassMetrics>,
+    /// Counts keyed by expected label, then by predicted label.
+    pub confusion: BTreeMap<Label, BTreeMap<Label, usize>>,
+    pub calibration: Calibration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClassMetrics {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Examples labeled with this class.
+    pub support: usize,
+}
+
+/// How well `confidence` matches the share of correct predictions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Non-empty confidence bins, lowest first.
+    pub bins: Vec<CalibrationBin>,
+    /// Mean gap between confidence and accuracy over the bins, weighted by
+    /// their size; 0 is perfectly calibrated.
+    pub expected_calibration_error: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    pub mean_confidence: f64,
+    pub accuracy: f64,
+}
+
+/// Analyzes every example with `analyzer` and scores the predictions.
+///
+/// The analyzer's breaker should be `CircuitBreaker::disabled`: once an
+/// ordinary breaker opens, the remaining examples fail without reaching the
+/// backend, which says nothing about its quality.
+pub async fn evaluate(analyzer: &TextAnalyzer, examples: &[LabeledExample]) -> Evaluation {
+    let texts: Vec<&str> = examples.iter().map(|e| e.text.as_str()).collect();
+    let results = analyzer.analyze_batch(&texts).await;
+    let outcomes: Vec<(Label, Option<Prediction>)> = examples
+        .iter()
+        .zip(results)
+        .map(|(example, result)| {
+            let prediction = match result {
+                Ok(analysis) => Some(Prediction { label: Label::of(&analysis.sentiment), confidence: analysis.confidence }),
+                Err(e) => {
+                    tracing::warn!(id = %example.id, kind = e.kind(), error = %e, "example failed");
+                    None
+                }
+            };
+            (example.label, prediction)
+        })
+        .collect();
+    Evaluation::from_predictions(analyzer.backend_name(), &outcomes)
+}
+
+impl Evaluation {
+    /// Scores expected labels against predictions, `None` marking failures,
+    /// which count as wrong.
+    pub fn from_predictions(backend: &str, outcomes: &[(Label, Option<Prediction>)]) -> Self {
+        let scored: Vec<(Label, Prediction)> =
+            outcomes.iter().filter_map(|(expected, prediction)| prediction.map(|p| (*expected, p))).collect();
+        let failed_of = |class: &Label| outcomes.iter().filter(|(expected, p)| expected == class && p.is_none()).count();
+
+        let mut confusion: BTreeMap<Label, BTreeMap<Label, usize>> = Label::ALL
+            .iter()
+            .map(|expected| (*expected, Label::ALL.iter().map(|predicted| (*predicted, 0)).collect()))
+            .collect();
+        for (expected, prediction) in &scored {
+            *confusion.get_mut(expected).unwrap().get_mut(&prediction.label).unwrap() += 1;
+        }
+
+        let correct = scored.iter().filter(|(expected, p)| *expected == p.label).count();
+        let mut present = 0;
+        let classes: BTreeMap<Label, ClassMetrics> = Label::ALL
+            .iter()
+            .map(|class| {
+                let hits = confusion[class][class];
+                let support = confusion[class].values().sum::<usize>() + failed_of(class);
+                let predicted: usize = confusion.values().map(|row| row[class]).sum();
+                present += usize::from(support + predicted > 0);
+                let precision = ratio(hits, predicted);
+                let recall = ratio(hits, support);
+                let f1 = if precision + recall > 0.0 { 2.0 * precision * recall / (precision + recall) } else { 0.0 };
+                (*class, ClassMetrics { precision, recall, f1, support })
+            })
+            .collect();
+
+        Self {
+            backend: backend.to_string(),
+            examples: outcomes.len(),
+            failed: outcomes.len() - scored.len(),
+            accuracy: ratio(correct, outcomes.len()),
+            macro_f1: classes.values().map(|c| c.f1).sum::<f64>() / present.max(1) as f64,
+            classes,
+            confusion,
+            calibration: Calibration::from_predictions(&scored),
+        }
+    }
+
+    /// Change of each headline figure from `baseline` to this evaluation.
+    pub fn compare(&self, baseline: &Evaluation) -> EvaluationDiff {
+        EvaluationDiff {
+            baseline_backend: baseline.backend.clone(),
+            accuracy: self.accuracy - baseline.accuracy,
+            macro_f1: self.macro_f1 - baseline.macro_f1,
+            f1: Label::ALL
+                .iter()
+                .map(|class| {
+                    let f1 = |e: &Evaluation| e.classes.get(class).map_or(0.0, |c| c.f1);
+                    (*class, f1(self) - f1(baseline))
+                })
+                .collect(),
+            expected_calibration_error: self.calibration.expected_calibration_error
+                - baseline.calibration.expected_calibration_error,
+            failed: self.failed as i64 - baseline.failed as i64,
+        }
+    }
+
+    /// Writes a human-readable report, with the comparison to a baseline if given.
+    pub fn write_report(&self, out: &mut dyn Write, diff: Option<&EvaluationDiff>) -> io::Result<()> {
+        writeln!(out, "📏 Evaluation of {} on {} examples ({} failed)", self.backend, self.examples, self.failed)?;
+        writeln!(out, "   Accuracy: {:.1}%", self.accuracy * 100.0)?;
+        writeln!(out, "   Macro F1: {:.3}", self.macro_f1)?;
+
+        writeln!(out, "\n{:<10} {:>9} {:>7} {:>7} {:>8}", "class", "precision", "recall", "f1", "support")?;
+        for (class, metrics) in &self.classes {
+            writeln!(
+                out,
+                "{:<10} {:>9.3} {:>7.3} {:>7.3} {:>8}",
+                class.name(),
+                metrics.precision,
+                metrics.recall,
+                metrics.f1,
+                metrics.support
+            )?;
+        }
+
+        write!(out, "\nConfusion (rows expected, columns predicted)\n{:<10}", "")?;
+        for class in Label::ALL {
+            write!(out, " {:>8}", class.name())?;
+        }
+        writeln!(out)?;
+        for (expected, row) in &self.confusion {
+            write!(out, "{:<10}", expected.name())?;
+            for class in Label::ALL {
+                write!(out, " {:>8}", row.get(&class).copied().unwrap_or(0))?;
+            }
+            writeln!(out)?;
+        }
+
+        writeln!(
+            out,
+            "\nCalibration (expected calibration error {:.3})",
+            self.calibration.expected_calibration_error
+        )?;
+        for bin in &self.calibration.bins {
+            writeln!(
+                out,
+                "   {:.1}-{:.1}: {:>5} predictions, mean confidence {:.3}, accuracy {:.3}",
+                bin.lower, bin.upper, bin.count, bin.mean_confidence, bin.accuracy
+            )?;
+        }
+
+        if let Some(diff) = diff {
+            writeln!(out, "\nChange from baseline ({})", diff.baseline_backend)?;
+            writeln!(out, "   Accuracy: {:+.1} points", diff.accuracy * 100.0)?;
+            writeln!(out, "   Macro F1: {:+.3}", diff.macro_f1)?;
+            for (class, delta) in &diff.f1 {
+                writeln!(out, "   F1 {}: {:+.3}", class.name(), delta)?;
+            }
+            writeln!(out, "   Calibration error: {:+.3}", diff.expected_calibration_error)?;
+            writeln!(out, "   Failed: {:+}", diff.failed)?;
+        }
+        Ok(())
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+impl Calibration {
+    fn from_predictions(scored: &[(Label, Prediction)]) -> Self {
+        let bin_count = (1.0 / CALIBRATION_BIN_WIDTH).round() as usize;
+        // (count, confidence sum, correct)
+        let mut sums = vec![(0usize, 0.0f64, 0usize); bin_count];
+        for (expected, prediction) in scored {
+            let confidence = prediction.confidence.clamp(0.0, 1.0);
+            let bin = ((confidence / CALIBRATION_BIN_WIDTH) as usize).min(bin_count - 1);
+            sums[bin].0 += 1;
+            sums[bin].1 += confidence;
+            sums[bin].2 += usize::from(*expected == prediction.label);
+        }
+
+        let bins: Vec<CalibrationBin> = sums
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (count, _, _))| *count > 0)
+            .map(|(i, (count, confidence, correct))| CalibrationBin {
+                lower: i as f64 / bin_count as f64,
+                upper: (i + 1) as f64 / bin_count as f64,
+                count,
+                mean_confidence: confidence / count as f64,
+                accuracy: ratio(correct, count),
+            })
+            .collect();
+        let expected_calibration_error = bins
+            .iter()
+            .map(|bin| bin.count as f64 / scored.len() as f64 * (bin.accuracy - bin.mean_confidence).abs())
+            .sum();
+        Self { bins, expected_calibration_error }
+    }
+}
+
+/// Differences from a baseline, positive when the figure went up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationDiff {
+    pub baseline_backend: String,
+    pub accuracy: f64,
+    pub macro_f1: f64,
+    pub f1: BTreeMap<Label, f64>,
+    /// Lower is better, so a positive value is a regression.
+    pub expected_calibration_error: f64,
+    pub failed: i64,
+}
+
+impl EvaluationDiff {
+    /// Whether accuracy or macro F1 fell by more than `tolerance`.
+    pub fn is_regression(&self, tolerance: f64) -> bool {
+        self.accuracy < -tolerance || self.macro_f1 < -tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Fault, LexiconBackend, ScriptStep, ScriptedBackend};
+    use crate::circuit_breaker::CircuitBreaker;
+    use std::sync::atomic::Ordering;
+
+    fn predicted(label: Label, confidence: f64) -> Option<Prediction> {
+        Some(Prediction { label, confidence })
+    }
+
+    #[test]
+    fn test_metrics_from_predictions() {
+        use Label::*;
+        let outcomes = [
+            (Positive, predicted(Positive, 0.9)),
+            (Positive, predicted(Positive, 0.8)),
+            (Positive, predicted(Neutral, 0.6)),
+            (Negative, predicted(Negative, 0.9)),
+            (Negative, predicted(Positive, 0.7)),
+            (Neutral, predicted(Neutral, 0.5)),
+            (Neutral, None),
+        ];
+        let evaluation = Evaluation::from_predictions("test", &outcomes);
+
+        assert_eq!((evaluation.examples, evaluation.failed), (7, 1));
+        // The failure counts as a wrong prediction
+        assert!((evaluation.accuracy - 4.0 / 7.0).abs() < 1e-9);
+        assert_eq!(evaluation.confusion[&Positive][&Neutral], 1);
+        assert_eq!(evaluation.confusion[&Negative][&Positive], 1);
+
+        let positive = evaluation.classes[&Positive];
+        assert!((positive.precision - 2.0 / 3.0).abs() < 1e-9);
+        assert!((positive.recall - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(positive.support, 3);
+        assert_eq!(evaluation.classes[&Negative].precision, 1.0);
+        assert_eq!(evaluation.classes[&Neutral].support, 2);
+        assert_eq!(evaluation.classes[&Neutral].recall, 0.5);
+    }
+
+    #[test]
+    fn test_calibration_bins() {
+        let outcomes = [
+            (Label::Positive, predicted(Label::Positive, 0.95)),
+            (Label::Positive, predicted(Label::Negative, 1.0)),
+            (Label::Neutral, predicted(Label::Neutral, 0.32)),
+        ];
+        let calibration = Evaluation::from_predictions("test", &outcomes).calibration;
+
+        let counts: Vec<(f64, usize)> = calibration.bins.iter().map(|b| (b.lower, b.count)).collect();
+        assert_eq!(counts, [(0.3, 1), (0.9, 2)]);
+        assert_eq!(calibration.bins[1].accuracy, 0.5);
+        // (|1 - 0.32| + 2 * |0.5 - 0.975|) / 3
+        assert!((calibration.expected_calibration_error - 0.5433333333333333).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_datasets() {
+        let jsonl = "{\"id\":\"a\",\"text\":\"Great\",\"label\":\"POS\"}\n\n{\"text\":\"Meh\",\"label\":\"neutral\"}\n";
+        let examples = parse_jsonl(jsonl).unwrap();
+        assert_eq!(examples[0], LabeledExample { id: "a".into(), text: "Great".into(), label: Label::Positive });
+        assert_eq!(examples[1].id, "3");
+
+        let csv = "text,label\n\"Bad, really\",negative\nFine,neu\n";
+        let examples = parse_csv(csv.as_bytes()).unwrap();
+        assert_eq!(examples[0].text, "Bad, really");
+        assert_eq!(examples[1], LabeledExample { id: "3".into(), text: "Fine".into(), label: Label::Neutral });
+
+        let error = parse_csv("text,label\nok,positive\nhmm,mixed\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 3: unknown label `mixed`"));
+    }
+
+    #[test]
+    fn test_compare_with_baseline() {
+        let baseline = Evaluation::from_predictions(
+            "old",
+            &[(Label::Positive, predicted(Label::Positive, 0.9)), (Label::Negative, predicted(Label::Negative, 0.9))],
+        );
+        let current = Evaluation::from_predictions(
+            "new",
+            &[(Label::Positive, predicted(Label::Positive, 0.9)), (Label::Negative, predicted(Label::Neutral, 0.9))],
+        );
+
+        let diff = current.compare(&baseline);
+        assert_eq!(diff.accuracy, -0.5);
+        assert_eq!(diff.f1[&Label::Negative], -1.0);
+        assert!(diff.is_regression(0.1));
+        assert!(!baseline.compare(&baseline).is_regression(0.0));
+
+        // Baselines round-trip through their JSON form
+        let saved: Evaluation = serde_json::from_str(&serde_json::to_string(&baseline).unwrap()).unwrap();
+        assert_eq!(current.compare(&saved).macro_f1, diff.macro_f1);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_lexicon_backend() {
+        let examples = parse_jsonl(
+            "{\"text\":\"I love this, it is excellent\",\"label\":\"positive\"}\n\
+             {\"text\":\"This is terrible and I hate it\",\"label\":\"negative\"}\n\
+             {\"text\":\"\",\"label\":\"neutral\"}\n",
+        )
+        .unwrap();
+        let analyzer = TextAnalyzer::with_backend(Box::new(LexiconBackend::default()));
+        let evaluation = evaluate(&analyzer, &examples).await;
+
+        assert_eq!(evaluation.backend, "lexicon");
+        assert_eq!(evaluation.failed, 1);
+        assert!((evaluation.accuracy - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(evaluation.classes[&Label::Positive].f1, 1.0);
+        assert_eq!(evaluation.classes[&Label::Neutral].recall, 0.0);
+
+        let mut report = Vec::new();
+        evaluation.write_report(&mut report, Some(&evaluation.compare(&evaluation))).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains("Accuracy: 66.7%"));
+        assert!(report.contains("Macro F1: +0.000"));
+    }
+
+    #[tokio::test]
+    async fn test_failing_backend_scores_badly() {
+        let examples: Vec<LabeledExample> = (0..10)
+            .map(|i| LabeledExample { id: i.to_string(), text: "I love it".into(), label: Label::Positive })
+            .collect();
+        let backend = ScriptedBackend::new([]).then(ScriptStep::fail(Fault::Api));
+        let calls = backend.call_counter();
+        let analyzer = TextAnalyzer::with_backend(Box::new(backend)).with_circuit_breaker(CircuitBreaker::disabled());
+        let evaluation = evaluate(&analyzer, &examples).await;
+
+        // Every example reached the backend, and every failure is a miss
+        assert_eq!(calls.load(Ordering::SeqCst), 10);
+        assert_eq!((evaluation.failed, evaluation.accuracy, evaluation.macro_f1), (10, 0.0, 0.0));
+
+        let baseline = Evaluation::from_predictions("old", &[(Label::Positive, predicted(Label::Positive, 0.9))]);
+        assert!(evaluation.compare(&baseline).is_regression(0.5));
+    }
+}
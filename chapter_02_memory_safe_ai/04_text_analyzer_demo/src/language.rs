@@ -0,0 +1,200 @@
+//! Offline language identification with character n-gram profiles.
+//!
+//! Each language is profiled by its most frequent 1- to 3-letter sequences
+//! (with word boundaries marked), ranked by frequency, as in Cavnar and
+//! Trenkle's "N-Gram-Based Text Categorization". A text is assigned the
+//! language whose ranking is closest to its own by the out-of-place measure.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// N-grams kept per profile.
+const PROFILE_SIZE: usize = 300;
+/// Longest n-gram, in characters including the `_` boundary marker.
+const MAX_NGRAM: usize = 3;
+/// Texts with fewer letters than this are too short to identify reliably.
+const MIN_LETTERS: usize = 10;
+/// Relative distance gap to the runner-up below which detection is ambiguous.
+const MIN_CONFIDENCE: f64 = 0.1;
+
+const SAMPLES: &[(Language, &str)] = &[
+    (Language::English, include_str!("../data/langid/en.txt")),
+    (Language::Spanish, include_str!("../data/langid/es.txt")),
+    (Language::German, include_str!("../data/langid/de.txt")),
+    (Language::French, include_str!("../data/langid/fr.txt")),
+    (Language::Portuguese, include_str!("../data/langid/pt.txt")),
+];
+
+/// Languages the detector can identify, serialized as ISO 639-1 codes.
+/// Identifying a language does not mean every backend can score it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Language {
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "es")]
+    Spanish,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "fr")]
+    French,
+    #[serde(rename = "pt")]
+    Portuguese,
+}
+
+impl Language {
+    pub const ALL: [Language; 5] =
+        [Language::English, Language::Spanish, Language::German, Language::French, Language::Portuguese];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Spanish => "es",
+            Language::German => "de",
+            Language::French => "fr",
+            Language::Portuguese => "pt",
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SAMPLES
+            .iter()
+            .map(|(language, _)| *language)
+            .find(|language| language.code() == s)
+            .ok_or_else(|| format!("unknown language `{}` (expected en, es, de, fr or pt)", s))
+    }
+}
+
+/// The language a text was analyzed as.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LanguageDetection {
+    pub language: Language,
+    /// How clearly the best profile beat the runner-up, in [0, 1]; 0 when the
+    /// text was too short or ambiguous and the default language was assumed.
+    pub confidence: f64,
+}
+
+/// Identifies the language of a text from the bundled sample profiles.
+#[derive(Debug, Clone)]
+pub struct LanguageDetector {
+    profiles: Vec<(Language, HashMap<String, usize>)>,
+}
+
+impl LanguageDetector {
+    pub fn new() -> Self {
+        let profiles = SAMPLES
+            .iter()
+            .map(|(language, sample)| {
+                let text = sample.lines().filter(|line| !line.starts_with('#')).collect::<Vec<_>>().join("\n");
+                let ranks = ranked_ngrams(&text).into_iter().enumerate().map(|(rank, g)| (g, rank));
+                (*language, ranks.collect())
+            })
+            .collect();
+        Self { profiles }
+    }
+
+    /// The closest language, or `None` when the text is too short or no
+    /// profile is clearly closer than the rest.
+    pub fn detect(&self, text: &str) -> Option<LanguageDetection> {
+        if text.chars().filter(|c| c.is_alphabetic()).count() < MIN_LETTERS {
+            return None;
+        }
+
+        let document = ranked_ngrams(text);
+        let mut distances: Vec<(Language, f64)> = self
+            .profiles
+            .iter()
+            .map(|(language, profile)| (*language, out_of_place(&document, profile)))
+            .collect();
+        distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let (language, best) = distances[0];
+        let runner_up = distances[1].1;
+        let confidence = if runner_up > 0.0 { (runner_up - best) / runner_up } else { 0.0 };
+        (confidence >= MIN_CONFIDENCE).then_some(LanguageDetection { language, confidence })
+    }
+}
+
+impl Default for LanguageDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Mean rank displacement of the document's n-grams in `profile`, in [0, 1]
+fn out_of_place(document: &[String], profile: &HashMap<String, usize>) -> f64 {
+    let total: usize = document
+        .iter()
+        .enumerate()
+        .map(|(rank, gram)| profile.get(gram).map_or(PROFILE_SIZE, |r| r.abs_diff(rank).min(PROFILE_SIZE)))
+        .sum();
+    total as f64 / (document.len().max(1) * PROFILE_SIZE) as f64
+}
+
+// The PROFILE_SIZE most frequent n-grams, most frequent first
+fn ranked_ngrams(text: &str) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in text.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()) {
+        let padded: Vec<char> = format!("_{}_", word.to_lowercase()).chars().collect();
+        for n in 1..=MAX_NGRAM {
+            for window in padded.windows(n) {
+                if window != ['_'] {
+                    *counts.entry(window.iter().collect()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    // Ties are broken alphabetically so profiles do not depend on hash order
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(PROFILE_SIZE);
+    ranked.into_iter().map(|(gram, _)| gram).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_languages() {
+        let detector = LanguageDetector::new();
+        let cases = [
+            ("The documentation could be improved, but the core functionality is solid.", Language::English),
+            ("Me encanta este producto, la entrega fue muy rápida y el precio es excelente.", Language::Spanish),
+            ("Das Produkt ist leider schlecht und der Kundenservice hat nie geantwortet.", Language::German),
+            ("Je suis très déçu, le colis est arrivé en retard et abîmé.", Language::French),
+            ("O atendimento foi péssimo e o produto chegou quebrado.", Language::Portuguese),
+        ];
+        for (text, expected) in cases {
+            let detection = detector.detect(text).unwrap_or_else(|| panic!("no language for {:?}", text));
+            assert_eq!(detection.language, expected, "{:?}", text);
+            assert!(detection.confidence > 0.0 && detection.confidence <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_short_text_is_inconclusive() {
+        let detector = LanguageDetector::new();
+        assert!(detector.detect("ok").is_none());
+        assert!(detector.detect("12345 67890 !!!").is_none());
+    }
+
+    #[test]
+    fn test_codes_round_trip() {
+        assert_eq!("de".parse::<Language>(), Ok(Language::German));
+        assert_eq!(serde_json::to_string(&Language::Spanish).unwrap(), "\"es\"");
+        assert!("xx".parse::<Language>().is_err());
+    }
+}
@@ -0,0 +1,31 @@
+//! Memory-safe text analysis: input sanitization, circuit breaking and
+//! pluggable sentiment backends.
+
+pub mod analysis;
+pub mod analyzer;
+pub mod backend;
+pub mod cache;
+pub mod chunking;
+pub mod circuit_breaker;
+pub mod confidence;
+pub mod emotion;
+pub mod error;
+pub mod evaluation;
+pub mod language;
+pub mod metrics;
+pub mod output;
+pub mod pipeline;
+pub mod redact;
+pub mod retry;
+pub mod sanitize;
+pub mod sentiment;
+pub mod server;
+pub mod text;
+pub mod topics;
+
+pub use analysis::{SentimentScore, TextAnalysis, Topic};
+pub use analyzer::TextAnalyzer;
+pub use backend::AnalysisBackend;
+pub use circuit_breaker::{BreakerState, CircuitBreaker, CircuitBreakerConfig};
+pub use error::AnalysisError;
+pub use sanitize::SanitizationPolicy;
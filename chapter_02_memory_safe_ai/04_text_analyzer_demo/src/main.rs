@@ -1,243 +1,491 @@
-
-use serde::{Deserialize, Serialize};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rust_ai_text_analyzer::backend::{LexiconBackend, MockBackend, OpenAiBackend, OpenAiConfig};
+use rust_ai_text_analyzer::cache::{AnalysisCache, CacheConfig};
+use rust_ai_text_analyzer::chunking::ChunkingConfig;
+use rust_ai_text_analyzer::emotion::EmotionLexicon;
+use rust_ai_text_analyzer::output::{
+    AnalysisRecord, CsvWriter, JsonLinesWriter, JsonWriter, ResultWriter, TextWriter,
+};
+use rust_ai_text_analyzer::evaluation::{self, Evaluation};
+use rust_ai_text_analyzer::language::Language;
+use rust_ai_text_analyzer::pipeline::{self, InputRecord};
+use rust_ai_text_analyzer::retry::RetryPolicy;
+use rust_ai_text_analyzer::sanitize::{LimitUnit, Normalization};
+use rust_ai_text_analyzer::server;
+use rust_ai_text_analyzer::topics::Taxonomy;
+use rust_ai_text_analyzer::{AnalysisBackend, CircuitBreaker, SanitizationPolicy, TextAnalyzer};
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tracing_subscriber::EnvFilter;
 
-// Core data structures - showing Rust's type safety
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TextAnalysis {
-    pub sentiment: SentimentScore,
-    pub key_topics: Vec<Topic>,
-    pub confidence: f64,
-    pub processing_time: Duration,
-}
+#[derive(Parser)]
+#[command(name = "rust-ai-text-analyzer", version, about = "Memory-safe sentiment and topic analysis")]
+struct Cli {
+    #[command(flatten)]
+    options: AnalyzerOptions,
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SentimentScore {
-    pub positive: f64,
-    pub negative: f64,
-    pub neutral: f64,
+    /// Runs the built-in demo when omitted
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Topic {
-    pub name: String,
-    pub relevance: f64,
-    pub category: String,
+#[derive(Subcommand)]
+enum Command {
+    /// Analyze a single text, read from stdin when no argument is given
+    Analyze { text: Option<String> },
+    /// Analyze every line of a JSON Lines file of `{"id": ..., "text": ...}` objects
+    Batch {
+        /// Input file, or `-` for stdin
+        #[arg(long, short)]
+        input: PathBuf,
+        /// Write latency, error, cache and breaker metrics as JSON to this file when done
+        #[arg(long)]
+        metrics: Option<PathBuf>,
+    },
+    /// Analyze JSON Lines as they are read, writing each result as soon as it is ready
+    Stream {
+        /// Input file, or `-` for stdin
+        #[arg(long, short, default_value = "-")]
+        input: PathBuf,
+        /// Write results to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Append to --output, skipping IDs it already holds results for and retrying failed ones (requires --format jsonl)
+        #[arg(long, requires = "output")]
+        resume: bool,
+    },
+    /// Score the analyzer against a labeled dataset
+    Evaluate {
+        /// CSV with `text` and `label` columns, or JSON Lines of `{"id", "text", "label"}` objects
+        #[arg(long, short)]
+        dataset: PathBuf,
+        /// Compare with the evaluation saved in this file
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        /// Save this evaluation to this file, to compare later runs with
+        #[arg(long)]
+        save_baseline: Option<PathBuf>,
+        /// Fail if accuracy or macro F1 drop by more than this fraction from --baseline
+        #[arg(long, requires = "baseline")]
+        max_regression: Option<f64>,
+    },
+    /// Analyze the built-in sample texts
+    Demo,
+    /// Serve the analysis API over HTTP until interrupted
+    Serve {
+        /// Address to listen on
+        #[arg(long, env = "ANALYZER_LISTEN_ADDR", default_value = "127.0.0.1:8080")]
+        addr: String,
+        /// Largest accepted request body, in bytes
+        #[arg(long, env = "ANALYZER_MAX_BODY_BYTES", default_value_t = server::DEFAULT_MAX_BODY_BYTES)]
+        max_body_bytes: usize,
+    },
 }
 
-// Error handling - explicit and safe
-#[derive(Debug)]
-pub enum AnalysisError {
-    EmptyInput,
-    InputTooLarge,
-    ApiError(String),
-    CircuitBreakerOpen,
-}
+#[derive(Args)]
+struct AnalyzerOptions {
+    /// Sentiment backend to send sanitized text to
+    #[arg(long, value_enum, env = "ANALYZER_BACKEND", default_value = "mock", global = true)]
+    backend: BackendKind,
 
-impl std::fmt::Display for AnalysisError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            AnalysisError::EmptyInput => write!(f, "Input text is empty"),
-            AnalysisError::InputTooLarge => write!(f, "Input text too large"),
-            AnalysisError::ApiError(msg) => write!(f, "API error: {}", msg),
-            AnalysisError::CircuitBreakerOpen => write!(f, "Circuit breaker open"),
-        }
-    }
-}
+    /// Largest accepted input, measured in --limit-unit
+    #[arg(long, env = "ANALYZER_MAX_INPUT_SIZE", default_value_t = 10_000, global = true)]
+    max_input_size: usize,
 
-impl std::error::Error for AnalysisError {}
+    /// Unit of --max-input-size: bytes, chars or tokens
+    #[arg(long, env = "ANALYZER_LIMIT_UNIT", default_value = "chars", global = true)]
+    limit_unit: LimitUnit,
 
-// Circuit breaker for safety
-pub struct CircuitBreaker {
-    failure_count: u32,
-    threshold: u32,
-}
+    /// Unicode normalization applied to input: none, nfc or nfkc
+    #[arg(long, env = "ANALYZER_NORMALIZATION", default_value = "nfc", global = true)]
+    normalization: Normalization,
 
-impl CircuitBreaker {
-    pub fn new() -> Self {
-        Self { failure_count: 0, threshold: 3 }
-    }
+    /// Analyze input over --max-input-size in sentence-aligned chunks instead of rejecting it
+    #[arg(long, env = "ANALYZER_CHUNK_LONG_INPUT", global = true)]
+    chunk_long_input: bool,
 
-    pub fn can_execute(&self) -> bool {
-        self.failure_count < self.threshold
-    }
+    /// Maximum number of chunks one input may be split into
+    #[arg(long, env = "ANALYZER_MAX_CHUNKS", default_value_t = ChunkingConfig::default().max_chunks, global = true)]
+    max_chunks: usize,
 
-    pub fn record_failure(&mut self) {
-        self.failure_count += 1;
-    }
+    /// Send emails, phone numbers, card numbers and IBANs to the backend unredacted
+    #[arg(long, env = "ANALYZER_KEEP_PII", global = true)]
+    keep_pii: bool,
 
-    pub fn record_success(&mut self) {
-        self.failure_count = 0;
-    }
+    /// Also score each sentence of the input separately, locally with the bundled lexicon
+    #[arg(long, env = "ANALYZER_SENTENCES", global = true)]
+    sentences: bool,
+
+    /// Also score anger, joy, sadness, fear, surprise and trust with the bundled English emotion lexicon
+    #[arg(long, env = "ANALYZER_EMOTIONS", global = true)]
+    emotions: bool,
+
+    /// Language (en, es, de, fr or pt) assumed for text too short to identify
+    #[arg(long, env = "ANALYZER_DEFAULT_LANGUAGE", default_value = "en", global = true)]
+    default_language: Language,
+
+    /// How results are written to stdout
+    #[arg(long, value_enum, env = "ANALYZER_OUTPUT_FORMAT", default_value = "text", global = true)]
+    format: OutputFormat,
+
+    /// Maximum concurrent backend calls in batch mode
+    #[arg(long, env = "ANALYZER_CONCURRENCY", default_value_t = 8, global = true)]
+    concurrency: usize,
+
+    /// Backend calls per analysis when transient errors are retried; 1 disables retries
+    #[arg(long, env = "ANALYZER_RETRY_ATTEMPTS", default_value_t = RetryPolicy::default().max_attempts, global = true)]
+    retry_attempts: u32,
+
+    /// Backoff before the first retry, doubling for each one after it
+    #[arg(long, env = "ANALYZER_RETRY_BASE_DELAY_MS", default_value_t = 200, global = true)]
+    retry_base_delay_ms: u64,
+
+    /// Longest wait between retries, including waits the backend asks for
+    #[arg(long, env = "ANALYZER_RETRY_MAX_DELAY_MS", default_value_t = 5000, global = true)]
+    retry_max_delay_ms: u64,
+
+    /// Abandon a backend call after this many milliseconds; it may then be retried
+    #[arg(long, env = "ANALYZER_CALL_TIMEOUT_MS", default_value_t = 30_000, global = true)]
+    call_timeout_ms: u64,
+
+    /// Fail an analysis not finished within this many milliseconds, retries included;
+    /// in batch mode the limit covers the whole batch
+    #[arg(long, env = "ANALYZER_DEADLINE_MS", global = true)]
+    deadline_ms: Option<u64>,
+
+    /// VADER-style lexicon file replacing the lexicon backend's bundled English one
+    #[arg(long, env = "ANALYZER_LEXICON", global = true)]
+    lexicon: Option<PathBuf>,
+
+    /// Topic taxonomy file of `term<TAB>category` lines for the offline backends
+    #[arg(long, env = "ANALYZER_TAXONOMY", global = true)]
+    taxonomy: Option<PathBuf>,
+
+    /// Cache up to this many results, skipping the backend for repeated texts
+    #[arg(long, env = "ANALYZER_CACHE_CAPACITY", global = true)]
+    cache_capacity: Option<usize>,
+
+    /// Seconds a cached result stays valid; 0 keeps results until evicted
+    #[arg(long, env = "ANALYZER_CACHE_TTL_SECS", default_value_t = 3600, global = true)]
+    cache_ttl_secs: u64,
+
+    /// File the cache is loaded from at startup and saved to on exit (enables the cache)
+    #[arg(long, env = "ANALYZER_CACHE_FILE", global = true)]
+    cache_file: Option<PathBuf>,
+
+    #[arg(long, env = "OPENAI_BASE_URL", default_value = "https://api.openai.com/v1", global = true)]
+    openai_base_url: String,
+
+    #[arg(long, env = "OPENAI_MODEL", default_value = "gpt-4o-mini", global = true)]
+    openai_model: String,
+
+    #[arg(long, env = "OPENAI_API_KEY", hide_env_values = true, global = true)]
+    openai_api_key: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum BackendKind {
+    /// Offline keyword matching with simulated latency
+    Mock,
+    /// Offline lexicon scoring with negation and intensifiers
+    Lexicon,
+    /// Any OpenAI-compatible chat completions API
+    Openai,
 }
 
-// Main analyzer - production patterns in action
-pub struct TextAnalyzer {
-    circuit_breaker: CircuitBreaker,
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable summary
+    Text,
+    /// Pretty-printed JSON array
+    Json,
+    /// One JSON object per line, streamed
+    Jsonl,
+    /// CSV with one sentiment score per column
+    Csv,
 }
 
-impl TextAnalyzer {
-    pub fn new() -> Result<Self, AnalysisError> {
-        Ok(Self {
-            circuit_breaker: CircuitBreaker::new(),
-        })
+impl OutputFormat {
+    fn writer(self) -> Box<dyn ResultWriter> {
+        self.writer_to(Box::new(io::stdout()))
     }
 
-    // Input validation - Rust's type system at work
-    pub fn sanitize_input(&self, input: &str) -> Result<String, AnalysisError> {
-        if input.is_empty() {
-            return Err(AnalysisError::EmptyInput);
+    fn writer_to(self, out: Box<dyn Write>) -> Box<dyn ResultWriter> {
+        match self {
+            OutputFormat::Text => Box::new(TextWriter::new(out)),
+            OutputFormat::Json => Box::new(JsonWriter::new(out)),
+            OutputFormat::Jsonl => Box::new(JsonLinesWriter::new(out)),
+            OutputFormat::Csv => Box::new(CsvWriter::new(out)),
         }
+    }
+}
 
-        if input.len() > 10_000 {
-            return Err(AnalysisError::InputTooLarge);
-        }
+impl AnalyzerOptions {
+    fn build_analyzer(&self) -> Result<TextAnalyzer, Box<dyn std::error::Error>> {
+        let taxonomy = match &self.taxonomy {
+            Some(path) => Taxonomy::load(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => Taxonomy::english(),
+        };
 
-        // Safe character filtering
-        let cleaned: String = input
-            .chars()
-            .filter(|c| c.is_alphanumeric() || c.is_whitespace() || ".,!?'-".contains(*c))
-            .collect();
+        let backend: Box<dyn AnalysisBackend> = match self.backend {
+            BackendKind::Mock => Box::new(MockBackend::new().with_taxonomy(taxonomy)),
+            BackendKind::Lexicon => {
+                let backend = match &self.lexicon {
+                    Some(path) => LexiconBackend::from_file(path)
+                        .map_err(|e| format!("{}: {}", path.display(), e))?,
+                    None => LexiconBackend::default(),
+                };
+                Box::new(backend.with_taxonomy(taxonomy))
+            }
+            BackendKind::Openai => {
+                let api_key = self
+                    .openai_api_key
+                    .clone()
+                    .ok_or("--openai-api-key or OPENAI_API_KEY is required for the openai backend")?;
+                Box::new(OpenAiBackend::new(OpenAiConfig {
+                    base_url: self.openai_base_url.clone(),
+                    api_key,
+                    model: self.openai_model.clone(),
+                }))
+            }
+        };
 
-        Ok(cleaned)
-    }
+        let sanitization = SanitizationPolicy {
+            max_input: self.max_input_size,
+            limit_unit: self.limit_unit,
+            normalization: self.normalization,
+            ..SanitizationPolicy::default()
+        };
 
-    // Main analysis function - all safety patterns together
-    pub async fn analyze_text(&mut self, input: &str) -> Result<TextAnalysis, AnalysisError> {
-        let start_time = Instant::now();
+        let mut analyzer = TextAnalyzer::with_backend(backend)
+            .with_sanitization_policy(sanitization)
+            .with_batch_concurrency(self.concurrency)
+            .with_default_language(self.default_language)
+            .with_sentence_breakdown(self.sentences)
+            .with_pii_redaction(!self.keep_pii)
+            .with_retry_policy(RetryPolicy {
+                max_attempts: self.retry_attempts.max(1),
+                base_delay: Duration::from_millis(self.retry_base_delay_ms),
+                max_delay: Duration::from_millis(self.retry_max_delay_ms),
+                ..RetryPolicy::default()
+            });
+        analyzer = analyzer.with_call_timeout(Duration::from_millis(self.call_timeout_ms));
+        if let Some(deadline) = self.deadline_ms {
+            analyzer = analyzer.with_deadline(Duration::from_millis(deadline));
+        }
+        if self.emotions {
+            analyzer = analyzer.with_emotions(EmotionLexicon::english());
+        }
+        if self.chunk_long_input {
+            analyzer = analyzer.with_chunking(ChunkingConfig { max_chunks: self.max_chunks, ..Default::default() });
+        }
 
-        // Step 1: Input validation
-        let sanitized = self.sanitize_input(input)?;
+        if self.cache_capacity.is_none() && self.cache_file.is_none() {
+            return Ok(analyzer);
+        }
+        let config = CacheConfig {
+            capacity: self.cache_capacity.unwrap_or(CacheConfig::default().capacity),
+            ttl: (self.cache_ttl_secs > 0).then(|| Duration::from_secs(self.cache_ttl_secs)),
+            path: self.cache_file.clone(),
+        };
+        let cache = AnalysisCache::open(config).map_err(|e| match &self.cache_file {
+            Some(path) => format!("{}: {}", path.display(), e),
+            None => e.to_string(),
+        })?;
+        Ok(analyzer.with_cache(cache))
+    }
+}
 
-        // Step 2: Circuit breaker check
-        if !self.circuit_breaker.can_execute() {
-            return Err(AnalysisError::CircuitBreakerOpen);
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    init_tracing();
+    let command = cli.command.unwrap_or(Command::Demo);
+    let mut analyzer = cli.options.build_analyzer()?;
+    if matches!(command, Command::Evaluate { .. }) {
+        // Every example has to reach the backend for the scores to mean anything
+        analyzer = analyzer.with_circuit_breaker(CircuitBreaker::disabled());
+    }
+    let analyzer = Arc::new(analyzer);
+    let mut writer = cli.options.format.writer();
+
+    match command {
+        Command::Analyze { text } => {
+            let text = match text {
+                Some(text) => text,
+                None => {
+                    let mut buffer = String::new();
+                    io::stdin().read_to_string(&mut buffer)?;
+                    buffer.trim_end().to_string()
+                }
+            };
+            let result = analyzer.analyze_text(&text).await;
+            writer.write_record(&AnalysisRecord::new("1", &text, &result))?;
+            writer.finish()?;
+            result?;
         }
+        Command::Batch { input, metrics } => {
+            let records = read_batch(&input)?;
+            let texts: Vec<&str> = records.iter().map(|(_, text)| text.as_str()).collect();
+            let results = analyzer.analyze_batch(&texts).await;
+            for ((id, text), result) in records.iter().zip(&results) {
+                writer.write_record(&AnalysisRecord::new(id, text, result))?;
+            }
+            writer.finish()?;
+            if let Some(path) = metrics {
+                let json = serde_json::to_vec_pretty(&analyzer.metrics().snapshot())?;
+                std::fs::write(&path, json).map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+        }
+        Command::Stream { input, output, resume } => {
+            let format = cli.options.format;
+            let (out, done): (Box<dyn Write>, HashSet<String>) = match &output {
+                Some(path) => {
+                    if resume && !matches!(format, OutputFormat::Jsonl) {
+                        return Err("--resume requires --format jsonl".into());
+                    }
+                    let context = |e: io::Error| format!("{}: {}", path.display(), e);
+                    let done = if resume { pipeline::prepare_resume(path).map_err(context)? } else { HashSet::new() };
+                    let file = OpenOptions::new()
+                        .create(true)
+                        .write(true)
+                        .append(resume)
+                        .truncate(!resume)
+                        .open(path)
+                        .map_err(context)?;
+                    (Box::new(file), done)
+                }
+                None => (Box::new(io::stdout()), HashSet::new()),
+            };
+
+            let mut writer = format.writer_to(out);
+            let summary = if input.as_os_str() == "-" {
+                pipeline::run(&analyzer, tokio::io::BufReader::new(tokio::io::stdin()), writer.as_mut(), &done).await
+            } else {
+                let file = tokio::fs::File::open(&input).await.map_err(|e| format!("{}: {}", input.display(), e))?;
+                pipeline::run(&analyzer, tokio::io::BufReader::new(file), writer.as_mut(), &done).await
+            }
+            .map_err(|e| format!("{}: {}", input.display(), e))?;
+            tracing::info!(
+                analyzed = summary.analyzed,
+                failed = summary.failed,
+                skipped = summary.skipped,
+                "stream finished"
+            );
+        }
+        Command::Evaluate { dataset, baseline, save_baseline, max_regression } => {
+            let examples = evaluation::load_dataset(&dataset).map_err(|e| format!("{}: {}", dataset.display(), e))?;
+            let result = evaluation::evaluate(&analyzer, &examples).await;
+            let diff = match &baseline {
+                Some(path) => {
+                    let context = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+                    let saved = std::fs::read(path).map_err(|e| context(&e))?;
+                    let saved: Evaluation = serde_json::from_slice(&saved).map_err(|e| context(&e))?;
+                    Some(result.compare(&saved))
+                }
+                None => None,
+            };
+
+            if matches!(cli.options.format, OutputFormat::Json | OutputFormat::Jsonl) {
+                let report = serde_json::json!({ "evaluation": result, "baseline_diff": diff });
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                result.write_report(&mut io::stdout(), diff.as_ref())?;
+            }
 
-        // Step 3: Safe AI processing (mocked for demo)
-        match self.mock_ai_call(&sanitized).await {
-            Ok(mut analysis) => {
-                self.circuit_breaker.record_success();
-                analysis.processing_time = start_time.elapsed();
-                Ok(analysis)
+            if let Some(path) = save_baseline {
+                let json = serde_json::to_vec_pretty(&result)?;
+                std::fs::write(&path, json).map_err(|e| format!("{}: {}", path.display(), e))?;
             }
-            Err(e) => {
-                self.circuit_breaker.record_failure();
-                Err(e)
+            if let (Some(diff), Some(tolerance)) = (&diff, max_regression) {
+                if diff.is_regression(tolerance) {
+                    return Err(format!("regressed by more than {} from the baseline", tolerance).into());
+                }
             }
         }
+        Command::Demo => run_demo(&analyzer).await?,
+        Command::Serve { addr, max_body_bytes } => {
+            tracing::info!(%addr, "listening");
+            server::serve(analyzer.clone(), addr, max_body_bytes).await?;
+        }
     }
 
-    // Mock AI call demonstrating the patterns
-    async fn mock_ai_call(&self, text: &str) -> Result<TextAnalysis, AnalysisError> {
-        // Simulate API call delay
-        tokio::time::sleep(Duration::from_millis(100)).await;
-
-        // Simple sentiment analysis for demo
-        let text_lower = text.to_lowercase();
-        let positive_words = ["love", "amazing", "great", "excellent"];
-        let negative_words = ["hate", "terrible", "awful", "bad"];
-
-        let positive_count = positive_words.iter()
-            .filter(|&word| text_lower.contains(word))
-            .count() as f64;
-        let negative_count = negative_words.iter()
-            .filter(|&word| text_lower.contains(word))
-            .count() as f64;
-
-        let total = positive_count + negative_count;
-        let (pos, neg, neu) = if total > 0.0 {
-            (positive_count / total, negative_count / total, 0.1)
-        } else {
-            (0.2, 0.2, 0.6)
-        };
+    if let Some(stats) = analyzer.cache_stats() {
+        tracing::info!(hits = stats.hits, misses = stats.misses, entries = stats.entries, "result cache");
+        analyzer.save_cache()?;
+    }
 
-        let sentiment = SentimentScore {
-            positive: pos,
-            negative: neg,
-            neutral: neu,
-        };
+    Ok(())
+}
 
-        let topics = vec![
-            Topic {
-                name: "product feedback".to_string(),
-                relevance: 0.9,
-                category: "business".to_string(),
-            }
-        ];
-
-        Ok(TextAnalysis {
-            sentiment,
-            key_topics: topics,
-            confidence: 0.85,
-            processing_time: Duration::from_millis(0), // Will be set by caller
-        })
-    }
+/// Logs to stderr, filtered by `RUST_LOG` (e.g. `rust_ai_text_analyzer=debug`),
+/// showing only warnings when it is unset.
+fn init_tracing() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+    tracing_subscriber::fmt().with_env_filter(filter).with_writer(io::stderr).init();
+}
 
-    // Batch processing for performance demo
-    pub async fn analyze_batch(&mut self, texts: &[&str]) -> Vec<Result<TextAnalysis, AnalysisError>> {
-        let mut results = Vec::new();
-        
-        for text in texts {
-            let result = self.analyze_text(text).await;
-            results.push(result);
+fn read_batch(path: &Path) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let reader: Box<dyn BufRead> = if path.as_os_str() == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(std::fs::File::open(path)?))
+    };
+
+    let mut records = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
         }
-        
-        results
+        let record: InputRecord = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: {}", path.display(), number + 1, e))?;
+        let id = record.id.unwrap_or_else(|| (number + 1).to_string());
+        records.push((id, record.text));
     }
+    Ok(records)
 }
 
 // Demo function showing everything in action
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn run_demo(analyzer: &TextAnalyzer) -> std::io::Result<()> {
     println!("🦀 Rust AI Text Analyzer Demo");
     println!("===============================\n");
 
-    let mut analyzer = TextAnalyzer::new()?;
-
     // Sample texts for demonstration
-    let sample_texts = vec![
+    let sample_texts = [
         "I absolutely love this new product! It's amazing!",
         "The service was okay, nothing special.",
         "This is terrible. I want my money back.",
-        "The documentation could be improved, but the core functionality is solid."
+        "The documentation could be improved, but the core functionality is solid.",
     ];
 
     let start_time = Instant::now();
 
     println!("📊 Batch Analysis Results:");
-    println!("==========================");
+    println!("==========================\n");
 
+    let mut writer = TextWriter::new(io::stdout());
     for (i, text) in sample_texts.iter().enumerate() {
-        println!("\n--- Text {} ---", i + 1);
-        
-        match analyzer.analyze_text(text).await {
-            Ok(analysis) => {
-                println!("📝 Text: {}", text);
-                println!("🎭 Sentiment:");
-                println!("   Positive: {:.1}%", analysis.sentiment.positive * 100.0);
-                println!("   Negative: {:.1}%", analysis.sentiment.negative * 100.0);
-                println!("   Neutral:  {:.1}%", analysis.sentiment.neutral * 100.0);
-                println!("🎯 Confidence: {:.1}%", analysis.confidence * 100.0);
-                println!("⏱️  Processing: {:?}", analysis.processing_time);
-                
-                let emoji = if analysis.sentiment.positive > 0.5 { "😊" } 
-                           else if analysis.sentiment.negative > 0.5 { "😞" } 
-                           else { "😐" };
-                println!("📊 Overall: {}", emoji);
-            }
-            Err(e) => {
-                println!("❌ Analysis failed: {}", e);
-            }
-        }
+        let result = analyzer.analyze_text(text).await;
+        writer.write_record(&AnalysisRecord::new(&(i + 1).to_string(), text, &result))?;
     }
+    writer.finish()?;
 
     let total_time = start_time.elapsed();
+    let metrics = analyzer.metrics().snapshot();
     println!("\n🏁 Performance Summary:");
     println!("=======================");
     println!("Total time: {:?}", total_time);
-    println!("Average per text: {:?}", total_time / sample_texts.len() as u32);
-    println!("Texts processed: {}", sample_texts.len());
+    for (backend, latency) in &metrics.backend_latency {
+        let average = Duration::from_secs_f64(latency.sum_seconds / latency.count.max(1) as f64);
+        println!("Average {} call: {:?} over {} calls", backend, average, latency.count);
+    }
+    println!("Texts processed: {}", metrics.requests);
+    println!("Errors: {}", metrics.errors.values().sum::<u64>());
 
     println!("\n✅ Safety Patterns Demonstrated:");
     println!("=================================");
@@ -249,46 +497,3 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[tokio::test]
-    async fn test_input_validation() {
-        let analyzer = TextAnalyzer::new().unwrap();
-        
-        // Test empty input
-        assert!(matches!(
-            analyzer.sanitize_input(""),
-            Err(AnalysisError::EmptyInput)
-        ));
-        
-        // Test valid input
-        let result = analyzer.sanitize_input("Hello, world!");
-        assert!(result.is_ok());
-    }
-
-    #[tokio::test]
-    async fn test_analysis() {
-        let mut analyzer = TextAnalyzer::new().unwrap();
-        let result = analyzer.analyze_text("I love this!").await;
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_circuit_breaker() {
-        let mut breaker = CircuitBreaker::new();
-        assert!(breaker.can_execute());
-        
-        // Simulate failures
-        for _ in 0..3 {
-            breaker.record_failure();
-        }
-        assert!(!breaker.can_execute());
-        
-        // Recovery
-        breaker.record_success();
-        assert!(breaker.can_execute());
-    }
-}
@@ -0,0 +1,304 @@
+//! In-process metrics for one analyzer: backend latency histograms, error
+//! counts by kind, cache lookups and circuit breaker transitions, exported
+//! as Prometheus text or as a JSON snapshot.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::circuit_breaker::BreakerState;
+use crate::error::AnalysisError;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+const BREAKER_STATES: [BreakerState; 3] = [BreakerState::Closed, BreakerState::Open, BreakerState::HalfOpen];
+
+/// Thread-safe registry the analyzer records into.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    requests: u64,
+    backend_latency: BTreeMap<String, Histogram>,
+    errors: BTreeMap<&'static str, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+    breaker_state: Option<BreakerState>,
+    breaker_transitions: BTreeMap<(BreakerState, BreakerState), u64>,
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    /// Observations per bucket, not cumulative; the last is the `+Inf` bucket.
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self { counts: vec![0; LATENCY_BUCKETS.len() + 1], sum: 0.0 }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let bucket = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound).unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += seconds;
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let buckets = LATENCY_BUCKETS
+            .iter()
+            .zip(&self.counts)
+            .map(|(bound, count)| {
+                cumulative += count;
+                Bucket { le: *bound, count: cumulative }
+            })
+            .collect();
+        HistogramSnapshot { buckets, count: self.counts.iter().sum(), sum_seconds: self.sum }
+    }
+}
+
+/// Everything recorded so far, as dumped at the end of a batch run.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    /// Calls to `analyze_text`, including those that failed.
+    pub requests: u64,
+    /// Backend call latency, keyed by backend name.
+    pub backend_latency: BTreeMap<String, HistogramSnapshot>,
+    /// Failed analyses, keyed by `AnalysisError::kind`.
+    pub errors: BTreeMap<String, u64>,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// The breaker state last observed, if the breaker has been consulted.
+    pub breaker_state: Option<BreakerState>,
+    pub breaker_transitions: Vec<BreakerTransition>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistogramSnapshot {
+    /// Cumulative counts, as in Prometheus; the remainder exceeded every bound.
+    pub buckets: Vec<Bucket>,
+    pub count: u64,
+    pub sum_seconds: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Bucket {
+    /// Upper bound in seconds.
+    pub le: f64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BreakerTransition {
+    pub from: BreakerState,
+    pub to: BreakerState,
+    pub count: u64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_request(&self) {
+        self.registry.lock().unwrap().requests += 1;
+    }
+
+    /// Records how long one call to the backend named `backend` took, whether
+    /// or not it succeeded.
+    pub fn record_backend_call(&self, backend: &str, elapsed: Duration) {
+        let mut registry = self.registry.lock().unwrap();
+        registry.backend_latency.entry(backend.to_string()).or_default().observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_error(&self, error: &AnalysisError) {
+        *self.registry.lock().unwrap().errors.entry(error.kind()).or_default() += 1;
+    }
+
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let mut registry = self.registry.lock().unwrap();
+        if hit {
+            registry.cache_hits += 1;
+        } else {
+            registry.cache_misses += 1;
+        }
+    }
+
+    /// Notes the breaker's current state, counting a transition when it
+    /// differs from the state last observed.
+    pub fn observe_breaker(&self, state: BreakerState) {
+        let mut registry = self.registry.lock().unwrap();
+        let previous = registry.breaker_state.replace(state).unwrap_or(BreakerState::Closed);
+        if previous != state {
+            *registry.breaker_transitions.entry((previous, state)).or_default() += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let registry = self.registry.lock().unwrap();
+        MetricsSnapshot {
+            requests: registry.requests,
+            backend_latency: registry
+                .backend_latency
+                .iter()
+                .map(|(backend, histogram)| (backend.clone(), histogram.snapshot()))
+                .collect(),
+            errors: registry.errors.iter().map(|(kind, count)| (kind.to_string(), *count)).collect(),
+            cache_hits: registry.cache_hits,
+            cache_misses: registry.cache_misses,
+            breaker_state: registry.breaker_state,
+            breaker_transitions: registry
+                .breaker_transitions
+                .iter()
+                .map(|((from, to), count)| BreakerTransition { from: *from, to: *to, count: *count })
+                .collect(),
+        }
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut out = String::new();
+
+        metric_header(&mut out, "analyzer_requests_total", "counter", "Calls to analyze_text, including failures.");
+        let _ = writeln!(out, "analyzer_requests_total {}", snapshot.requests);
+
+        metric_header(&mut out, "analyzer_backend_latency_seconds", "histogram", "Latency of backend calls.");
+        for (backend, histogram) in &snapshot.backend_latency {
+            let backend = escape_label(backend);
+            for bucket in &histogram.buckets {
+                let _ = writeln!(
+                    out,
+                    "analyzer_backend_latency_seconds_bucket{{backend=\"{}\",le=\"{}\"}} {}",
+                    backend, bucket.le, bucket.count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "analyzer_backend_latency_seconds_bucket{{backend=\"{}\",le=\"+Inf\"}} {}",
+                backend, histogram.count
+            );
+            let _ = writeln!(out, "analyzer_backend_latency_seconds_sum{{backend=\"{}\"}} {}", backend, histogram.sum_seconds);
+            let _ = writeln!(out, "analyzer_backend_latency_seconds_count{{backend=\"{}\"}} {}", backend, histogram.count);
+        }
+
+        metric_header(&mut out, "analyzer_errors_total", "counter", "Failed analyses by error kind.");
+        for (kind, count) in &snapshot.errors {
+            let _ = writeln!(out, "analyzer_errors_total{{kind=\"{}\"}} {}", kind, count);
+        }
+
+        metric_header(&mut out, "analyzer_cache_hits_total", "counter", "Results served from the cache.");
+        let _ = writeln!(out, "analyzer_cache_hits_total {}", snapshot.cache_hits);
+        metric_header(&mut out, "analyzer_cache_misses_total", "counter", "Cache lookups that found no result.");
+        let _ = writeln!(out, "analyzer_cache_misses_total {}", snapshot.cache_misses);
+
+        metric_header(&mut out, "analyzer_circuit_breaker_state", "gauge", "1 for the breaker's current state.");
+        let current = snapshot.breaker_state.unwrap_or(BreakerState::Closed);
+        for state in BREAKER_STATES {
+            let _ = writeln!(
+                out,
+                "analyzer_circuit_breaker_state{{state=\"{}\"}} {}",
+                state.name(),
+                u8::from(state == current)
+            );
+        }
+
+        metric_header(
+            &mut out,
+            "analyzer_circuit_breaker_transitions_total",
+            "counter",
+            "Circuit breaker state changes.",
+        );
+        for transition in &snapshot.breaker_transitions {
+            let _ = writeln!(
+                out,
+                "analyzer_circuit_breaker_transitions_total{{from=\"{}\",to=\"{}\"}} {}",
+                transition.from.name(),
+                transition.to.name(),
+                transition.count
+            );
+        }
+
+        out
+    }
+}
+
+fn metric_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        for millis in [3, 40, 40, 20_000] {
+            metrics.record_backend_call("mock", Duration::from_millis(millis));
+        }
+
+        let histogram = &metrics.snapshot().backend_latency["mock"];
+        assert_eq!(histogram.count, 4);
+        assert_eq!(histogram.buckets[0].count, 1);
+        assert_eq!(histogram.buckets[3].count, 3);
+        assert_eq!(histogram.buckets.last().unwrap().count, 3);
+        assert!((histogram.sum_seconds - 20.083).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_breaker_transitions_are_counted_once() {
+        let metrics = Metrics::new();
+        for state in [BreakerState::Closed, BreakerState::Open, BreakerState::Open, BreakerState::HalfOpen] {
+            metrics.observe_breaker(state);
+        }
+
+        let transitions = metrics.snapshot().breaker_transitions;
+        assert_eq!(
+            transitions,
+            [
+                BreakerTransition { from: BreakerState::Closed, to: BreakerState::Open, count: 1 },
+                BreakerTransition { from: BreakerState::Open, to: BreakerState::HalfOpen, count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prometheus_text() {
+        let metrics = Metrics::new();
+        metrics.record_request();
+        metrics.record_error(&AnalysisError::EmptyInput);
+        metrics.record_backend_call("lexicon", Duration::from_millis(2));
+        metrics.record_cache_lookup(true);
+        metrics.observe_breaker(BreakerState::Open);
+
+        let text = metrics.to_prometheus();
+        for line in [
+            "# TYPE analyzer_backend_latency_seconds histogram",
+            "analyzer_requests_total 1",
+            "analyzer_errors_total{kind=\"empty_input\"} 1",
+            "analyzer_backend_latency_seconds_bucket{backend=\"lexicon\",le=\"0.005\"} 1",
+            "analyzer_backend_latency_seconds_bucket{backend=\"lexicon\",le=\"+Inf\"} 1",
+            "analyzer_cache_hits_total 1",
+            "analyzer_circuit_breaker_state{state=\"open\"} 1",
+            "analyzer_circuit_breaker_transitions_total{from=\"closed\",to=\"open\"} 1",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {:?} in\n{}", line, text);
+        }
+    }
+}
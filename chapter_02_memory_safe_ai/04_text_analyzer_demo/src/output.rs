@@ -0,0 +1,327 @@
+//! Writers that turn analysis results into human or machine-readable output.
+
+use serde::Serialize;
+use std::io::{self, Write};
+
+use crate::analysis::TextAnalysis;
+use crate::emotion::Emotion;
+use crate::error::AnalysisError;
+
+/// Version of the record layout below; bump on any breaking field change.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One analyzed input as it appears in every machine-readable format.
+#[derive(Debug, Serialize)]
+pub struct AnalysisRecord<'a> {
+    pub schema_version: u32,
+    pub id: &'a str,
+    pub text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<&'a TextAnalysis>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl<'a> AnalysisRecord<'a> {
+    pub fn new(id: &'a str, text: &'a str, result: &'a Result<TextAnalysis, AnalysisError>) -> Self {
+        let (analysis, error) = match result {
+            Ok(analysis) => (Some(analysis), None),
+            Err(e) => (None, Some(error_chain(e))),
+        };
+        Self { schema_version: SCHEMA_VERSION, id, text, analysis, error }
+    }
+}
+
+/// An error message followed by each of its causes, separated by `: `.
+pub fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Streams records to an output; `finish` must be called once all are written.
+pub trait ResultWriter {
+    fn write_record(&mut self, record: &AnalysisRecord) -> io::Result<()>;
+
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+/// The emoji-decorated summary used by the demo.
+pub struct TextWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> TextWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, written: 0 }
+    }
+}
+
+impl<W: Write> ResultWriter for TextWriter<W> {
+    fn write_record(&mut self, record: &AnalysisRecord) -> io::Result<()> {
+        if self.written > 0 {
+            writeln!(self.out)?;
+        }
+        self.written += 1;
+
+        writeln!(self.out, "--- Text {} ---", record.id)?;
+        let analysis = match (record.analysis, &record.error) {
+            (Some(analysis), _) => analysis,
+            (None, error) => {
+                let message = error.as_deref().unwrap_or("unknown error");
+                return writeln!(self.out, "❌ Analysis failed: {}", message);
+            }
+        };
+
+        writeln!(self.out, "📝 Text: {}", record.text)?;
+        writeln!(self.out, "🎭 Sentiment:")?;
+        writeln!(self.out, "   Positive: {:.1}%", analysis.sentiment.positive * 100.0)?;
+        writeln!(self.out, "   Negative: {:.1}%", analysis.sentiment.negative * 100.0)?;
+        writeln!(self.out, "   Neutral:  {:.1}%", analysis.sentiment.neutral * 100.0)?;
+        writeln!(self.out, "🎯 Confidence: {:.1}%", analysis.confidence * 100.0)?;
+        writeln!(self.out, "⏱️  Processing: {:?}", analysis.processing_time)?;
+        writeln!(self.out, "🔖 Request ID: {}", analysis.request_id)?;
+        if let Some(detection) = &analysis.language {
+            writeln!(self.out, "🌐 Language: {} ({:.0}% confidence)", detection.language, detection.confidence * 100.0)?;
+        }
+        if !analysis.redactions.is_empty() {
+            let redactions: Vec<String> =
+                analysis.redactions.iter().map(|(kind, count)| format!("{} {}", count, kind.name())).collect();
+            writeln!(self.out, "🔒 Redacted: {}", redactions.join(", "))?;
+        }
+        if let Some(emotions) = &analysis.emotions {
+            match emotions.dominant() {
+                Some(dominant) => {
+                    let shares: Vec<String> = Emotion::ALL
+                        .iter()
+                        .filter(|emotion| emotions.get(**emotion) > 0.0)
+                        .map(|emotion| format!("{} {:.0}%", emotion, emotions.get(*emotion) * 100.0))
+                        .collect();
+                    writeln!(self.out, "💬 Emotion: {} ({})", dominant, shares.join(", "))?;
+                }
+                None => writeln!(self.out, "💬 Emotion: none detected")?,
+            }
+        }
+        if analysis.attempts > 1 {
+            writeln!(self.out, "🔁 Attempts: {}", analysis.attempts)?;
+        }
+        if !analysis.chunks.is_empty() {
+            writeln!(self.out, "🧩 Chunks: {}", analysis.chunks.len())?;
+        }
+        if !analysis.sentences.is_empty() {
+            writeln!(self.out, "🗒️  Sentences:")?;
+            for sentence in &analysis.sentences {
+                let text = record.text.get(sentence.start..sentence.end).unwrap_or_default();
+                writeln!(
+                    self.out,
+                    "   +{:.0}% / -{:.0}%  {}",
+                    sentence.sentiment.positive * 100.0,
+                    sentence.sentiment.negative * 100.0,
+                    text
+                )?;
+            }
+        }
+
+        let emoji = if analysis.sentiment.positive > 0.5 { "😊" }
+                   else if analysis.sentiment.negative > 0.5 { "😞" }
+                   else { "😐" };
+        writeln!(self.out, "📊 Overall: {}", emoji)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// A pretty-printed JSON array of records, written incrementally.
+pub struct JsonWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, written: 0 }
+    }
+}
+
+impl<W: Write> ResultWriter for JsonWriter<W> {
+    fn write_record(&mut self, record: &AnalysisRecord) -> io::Result<()> {
+        self.out.write_all(if self.written == 0 { b"[\n" } else { b",\n" })?;
+        self.written += 1;
+        serde_json::to_writer_pretty(&mut self.out, record)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.write_all(if self.written == 0 { b"[]\n" } else { b"\n]\n" })?;
+        self.out.flush()
+    }
+}
+
+/// One compact JSON object per line, flushed as each record arrives.
+pub struct JsonLinesWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> ResultWriter for JsonLinesWriter<W> {
+    fn write_record(&mut self, record: &AnalysisRecord) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, record)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// CSV with sentiment flattened into columns and topic names joined by `;`.
+pub struct CsvWriter<W: Write> {
+    out: csv::Writer<W>,
+}
+
+// New columns go at the end, so readers going by position keep working
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    schema_version: u32,
+    id: &'a str,
+    text: &'a str,
+    positive: Option<f64>,
+    negative: Option<f64>,
+    neutral: Option<f64>,
+    confidence: Option<f64>,
+    processing_time_ms: Option<f64>,
+    topics: Option<String>,
+    error: Option<&'a str>,
+    request_id: Option<String>,
+    language: Option<&'static str>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out: csv::Writer::from_writer(out) }
+    }
+}
+
+impl<W: Write> ResultWriter for CsvWriter<W> {
+    fn write_record(&mut self, record: &AnalysisRecord) -> io::Result<()> {
+        let analysis = record.analysis;
+        let row = CsvRow {
+            schema_version: record.schema_version,
+            id: record.id,
+            text: record.text,
+            positive: analysis.map(|a| a.sentiment.positive),
+            negative: analysis.map(|a| a.sentiment.negative),
+            neutral: analysis.map(|a| a.sentiment.neutral),
+            confidence: analysis.map(|a| a.confidence),
+            processing_time_ms: analysis.map(|a| a.processing_time.as_secs_f64() * 1000.0),
+            topics: analysis.map(|a| {
+                a.key_topics.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(";")
+            }),
+            error: record.error.as_deref(),
+            request_id: analysis.map(|a| a.request_id.to_string()),
+            language: analysis.and_then(|a| a.language).map(|d| d.language.code()),
+        };
+        self.out.serialize(row)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{SentimentScore, Topic};
+    use std::time::Duration;
+
+    fn sample() -> TextAnalysis {
+        TextAnalysis {
+            sentiment: SentimentScore { positive: 0.75, negative: 0.25, neutral: 0.0 },
+            key_topics: vec![
+                Topic {
+                    name: "pricing".to_string(),
+                    relevance: 0.8,
+                    category: "business".to_string(),
+                    sentiment: None,
+                    mentions: Vec::new(),
+                },
+                Topic {
+                    name: "support".to_string(),
+                    relevance: 0.5,
+                    category: "service".to_string(),
+                    sentiment: None,
+                    mentions: Vec::new(),
+                },
+            ],
+            confidence: 0.5,
+            processing_time: Duration::from_micros(1500),
+            ..TextAnalysis::neutral_for_test()
+        }
+    }
+
+    #[test]
+    fn test_json_lines_schema() {
+        let ok = Ok(sample());
+        let failed = Err(AnalysisError::EmptyInput);
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer.write_record(&AnalysisRecord::new("a", "Good, pricey", &ok)).unwrap();
+        writer.write_record(&AnalysisRecord::new("b", "", &failed)).unwrap();
+        writer.finish().unwrap();
+
+        let out = String::from_utf8(writer.out).unwrap();
+        let lines: Vec<serde_json::Value> =
+            out.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines[0]["schema_version"], SCHEMA_VERSION);
+        assert_eq!(lines[0]["analysis"]["processing_time_ms"], 1.5);
+        assert!(lines[0].get("error").is_none());
+        assert_eq!(lines[1]["error"], "Input text is empty");
+        assert!(lines[1].get("analysis").is_none());
+
+        // The analysis round-trips through its own serialized form
+        let parsed: TextAnalysis = serde_json::from_value(lines[0]["analysis"].clone()).unwrap();
+        assert_eq!(parsed.processing_time, Duration::from_micros(1500));
+    }
+
+    #[test]
+    fn test_csv_flattens_sentiment() {
+        let ok = Ok(sample());
+        let mut writer = CsvWriter::new(Vec::new());
+        writer.write_record(&AnalysisRecord::new("1", "Good, pricey", &ok)).unwrap();
+        writer.finish().unwrap();
+
+        let out = String::from_utf8(writer.out.into_inner().unwrap()).unwrap();
+        let mut lines = out.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "schema_version,id,text,positive,negative,neutral,confidence,processing_time_ms,topics,error,request_id,language"
+        );
+        assert_eq!(lines.next().unwrap(), "1,1,\"Good, pricey\",0.75,0.25,0.0,0.5,1.5,pricing;support,,00000000-0000-0000-0000-000000000000,");
+    }
+
+    #[test]
+    fn test_empty_json_array() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.finish().unwrap();
+        assert_eq!(writer.out, b"[]\n");
+    }
+}
@@ -0,0 +1,206 @@
+//! Streaming analysis of JSON Lines input: records are read, analyzed and
+//! written one at a time, so the corpus never has to fit in memory.
+
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::analyzer::TextAnalyzer;
+use crate::output::{AnalysisRecord, ResultWriter};
+
+/// A JSON Lines input record; `id` defaults to the 1-based line number.
+#[derive(Debug, Deserialize)]
+pub struct InputRecord {
+    pub id: Option<String>,
+    pub text: String,
+}
+
+/// What a pipeline run did with its input records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineSummary {
+    pub analyzed: usize,
+    pub failed: usize,
+    /// Records whose ID was in the skip set.
+    pub skipped: usize,
+}
+
+/// Analyzes the JSON Lines records read from `reader`, writing each result as
+/// soon as it is ready, so output order can differ from input order.
+///
+/// At most the analyzer's batch concurrency records are in flight, and lines
+/// are only read as slots free up, so a slow backend slows reading instead of
+/// buffering the input. Records whose ID is in `skip` are not analyzed. A
+/// malformed line stops the run with `InvalidData`; records still in flight
+/// then are not written, so a resumed run analyzes them again.
+pub async fn run<R: AsyncBufRead + Unpin>(
+    analyzer: &TextAnalyzer,
+    reader: R,
+    writer: &mut dyn ResultWriter,
+    skip: &HashSet<String>,
+) -> io::Result<PipelineSummary> {
+    let records = stream::unfold((reader.lines(), 0), |(mut lines, mut number)| async move {
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => return None,
+                Err(e) => return Some((Err(e), (lines, number))),
+            };
+            number += 1;
+            if !line.trim().is_empty() {
+                return Some((parse_line(&line, number), (lines, number)));
+            }
+        }
+    });
+
+    let results = records
+        .map(|record| async move {
+            let (id, text) = record?;
+            if skip.contains(&id) {
+                return Ok(None);
+            }
+            let result = analyzer.analyze_text(&text).await;
+            Ok::<_, io::Error>(Some((id, text, result)))
+        })
+        .buffer_unordered(analyzer.batch_concurrency());
+    let mut results = std::pin::pin!(results);
+
+    let mut summary = PipelineSummary::default();
+    while let Some(outcome) = results.next().await {
+        let Some((id, text, result)) = outcome? else {
+            summary.skipped += 1;
+            continue;
+        };
+        writer.write_record(&AnalysisRecord::new(&id, &text, &result))?;
+        match result {
+            Ok(_) => summary.analyzed += 1,
+            Err(_) => summary.failed += 1,
+        }
+    }
+    writer.finish()?;
+    Ok(summary)
+}
+
+fn parse_line(line: &str, number: usize) -> io::Result<(String, String)> {
+    let record: InputRecord = serde_json::from_str(line)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number, e)))?;
+    Ok((record.id.unwrap_or_else(|| number.to_string()), record.text))
+}
+
+/// IDs of the records analyzed successfully in the JSON Lines output at
+/// `path`, for resuming an interrupted run by appending to it. Records that
+/// failed, e.g. while the backend was down, are removed from the file so
+/// their retry's line replaces them, as is a partial last line left by the
+/// interruption. A missing file has no IDs.
+pub fn prepare_resume(path: &Path) -> io::Result<HashSet<String>> {
+    #[derive(Deserialize)]
+    struct WrittenRecord {
+        id: String,
+        error: Option<String>,
+    }
+
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e),
+    };
+
+    let complete = contents.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    let mut ids = HashSet::new();
+    let mut kept = Vec::with_capacity(complete);
+    for (number, line) in contents[..complete].split(|b| *b == b'\n').enumerate() {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let record: WrittenRecord = serde_json::from_slice(line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, e))
+        })?;
+        if record.error.is_none() {
+            ids.insert(record.id);
+            kept.extend_from_slice(line);
+            kept.push(b'\n');
+        }
+    }
+
+    if kept.len() < contents.len() {
+        // Written beside the output and renamed over it, so an interruption
+        // here leaves either the old file or the new one
+        let temp = path.with_extension("resume.tmp");
+        fs::write(&temp, &kept)?;
+        fs::rename(&temp, path)?;
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+    use crate::output::JsonLinesWriter;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn written_ids(output: &[u8]) -> Vec<String> {
+        let mut ids: Vec<String> = output
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice::<serde_json::Value>(line).unwrap()["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_streams_records_and_skips_done_ids() {
+        let analyzer = TextAnalyzer::with_backend(Box::new(MockBackend::with_latency(Duration::ZERO)));
+        let input = b"{\"id\":\"a\",\"text\":\"I love it\"}\n\n{\"text\":\"\"}\n{\"id\":\"c\",\"text\":\"awful\"}\n";
+        let skip = HashSet::from(["c".to_string()]);
+
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        let summary = run(&analyzer, &input[..], &mut writer, &skip).await.unwrap();
+        assert_eq!(summary, PipelineSummary { analyzed: 1, failed: 1, skipped: 1 });
+        // The unnamed record is identified by its line number
+        assert_eq!(written_ids(&writer.into_inner()), ["3", "a"]);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_line_stops_the_run() {
+        let analyzer = TextAnalyzer::with_backend(Box::new(MockBackend::with_latency(Duration::ZERO)));
+        let input = b"{\"id\":\"a\",\"text\":\"fine\"}\nnot json\n";
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        let error = run(&analyzer, &input[..], &mut writer, &HashSet::new()).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn test_resume_retries_failed_records() {
+        let path = std::env::temp_dir().join(format!("pipeline-{}.jsonl", Uuid::new_v4()));
+        let done = "{\"id\":\"a\",\"text\":\"x\",\"analysis\":{}}\n";
+        fs::write(&path, format!("{}{{\"id\":\"b\",\"text\":\"y\",\"error\":\"Circuit breaker open\"}}\n", done)).unwrap();
+
+        let ids = prepare_resume(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(ids, HashSet::from(["a".to_string()]));
+        assert_eq!(contents, done);
+    }
+
+    #[test]
+    fn test_resume_truncates_partial_line() {
+        let path = std::env::temp_dir().join(format!("pipeline-{}.jsonl", Uuid::new_v4()));
+        fs::write(&path, "{\"id\":\"a\",\"text\":\"x\"}\n{\"id\":\"b\",\"text\":\"y\"}\n{\"id\":\"c\",\"te").unwrap();
+
+        let ids = prepare_resume(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(ids, HashSet::from(["a".to_string(), "b".to_string()]));
+        assert!(contents.ends_with("\"y\"}\n"));
+        assert!(prepare_resume(&path).unwrap().is_empty());
+    }
+}
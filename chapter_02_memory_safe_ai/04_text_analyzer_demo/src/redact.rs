@@ -0,0 +1,345 @@
+//! Detection and redaction of personal data before text is sent to a
+//! backend: email addresses, phone numbers, payment card numbers and IBANs
+//! are replaced with typed placeholders such as `[EMAIL]`.
+//!
+//! Card numbers must pass the Luhn check and IBANs their mod-97 checksum, and
+//! phone numbers need a leading `+`, phone-style grouping or a `tel:` label,
+//! so order numbers and other long digit runs are left alone.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::text::OffsetMap;
+
+/// Kinds of personal data that are redacted, in the order they are looked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    Iban,
+    CreditCard,
+    Phone,
+}
+
+impl PiiKind {
+    pub const ALL: [PiiKind; 4] = [PiiKind::Email, PiiKind::Iban, PiiKind::CreditCard, PiiKind::Phone];
+
+    /// The text that replaces an occurrence.
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            PiiKind::Email => "[EMAIL]",
+            PiiKind::Iban => "[IBAN]",
+            PiiKind::CreditCard => "[CARD]",
+            PiiKind::Phone => "[PHONE]",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PiiKind::Email => "email",
+            PiiKind::Iban => "iban",
+            PiiKind::CreditCard => "credit_card",
+            PiiKind::Phone => "phone",
+        }
+    }
+
+    fn find(self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            PiiKind::Email => find_emails(text),
+            PiiKind::Iban => find_ibans(text),
+            PiiKind::CreditCard => find_card_numbers(text),
+            PiiKind::Phone => find_phone_numbers(text),
+        }
+    }
+}
+
+/// Whether `text` is exactly one of the placeholders, e.g. to keep them out
+/// of extracted topics.
+pub fn is_placeholder(text: &str) -> bool {
+    PiiKind::ALL.iter().any(|kind| kind.placeholder() == text)
+}
+
+/// Text with personal data replaced, and how many of each kind were found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redacted {
+    pub text: String,
+    pub counts: BTreeMap<PiiKind, usize>,
+    /// Maps byte offsets in `text` back to the text that was redacted.
+    pub offsets: OffsetMap,
+}
+
+/// Replaces every email address, IBAN, card number and phone number in
+/// `text` with its placeholder. Where matches overlap, the kind earlier in
+/// `PiiKind::ALL` wins.
+pub fn redact(text: &str) -> Redacted {
+    let mut found: Vec<(Range<usize>, PiiKind)> = Vec::new();
+    for kind in PiiKind::ALL {
+        for range in kind.find(text) {
+            if !found.iter().any(|(taken, _)| range.start < taken.end && taken.start < range.end) {
+                found.push((range, kind));
+            }
+        }
+    }
+    found.sort_by_key(|(range, _)| range.start);
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut counts = BTreeMap::new();
+    let mut offsets = OffsetMap::default();
+    let mut copied = 0;
+    for (range, kind) in found {
+        let pieces = [(&text[copied..range.start], copied..range.start), (kind.placeholder(), range.clone())];
+        for (piece, original) in pieces {
+            offsets.push(redacted.len()..redacted.len() + piece.len(), original);
+            redacted.push_str(piece);
+        }
+        *counts.entry(kind).or_default() += 1;
+        copied = range.end;
+    }
+    offsets.push(redacted.len()..redacted.len() + text.len() - copied, copied..text.len());
+    redacted.push_str(&text[copied..]);
+    Redacted { text: redacted, counts, offsets }
+}
+
+fn is_email_local(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._%+-".contains(c)
+}
+
+fn is_email_domain(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '.' || c == '-'
+}
+
+// `local@domain.tld`, with a domain of at least two labels and a letters-only TLD
+fn find_emails(text: &str) -> Vec<Range<usize>> {
+    let mut emails = Vec::new();
+    for (at, _) in text.match_indices('@') {
+        let start = text[..at]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_email_local(*c))
+            .last()
+            .map_or(at, |(i, _)| i);
+        let local = text[start..at].trim_start_matches('.');
+        let start = at - local.len();
+
+        let domain_len = text[at + 1..].chars().take_while(|c| is_email_domain(*c)).count();
+        let domain = text[at + 1..at + 1 + domain_len].trim_end_matches(['.', '-']);
+        let valid_domain = domain
+            .rsplit_once('.')
+            .is_some_and(|(host, tld)| !host.is_empty() && tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()));
+
+        if !local.is_empty() && valid_domain && emails.last().is_none_or(|e: &Range<usize>| e.end <= start) {
+            emails.push(start..at + 1 + domain.len());
+        }
+    }
+    emails
+}
+
+// Runs of ASCII digits joined by single separators from `separators`, not
+// touching other alphanumerics. Returns each run's range and its digits.
+fn digit_runs(text: &str, separators: &str) -> Vec<(Range<usize>, String)> {
+    let bytes = text.as_bytes();
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let starts_run = bytes[i].is_ascii_digit() && !text[..i].chars().next_back().is_some_and(char::is_alphanumeric);
+        if !starts_run {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut digits = String::new();
+        let mut end = i;
+        while i < bytes.len() {
+            if bytes[i].is_ascii_digit() {
+                digits.push(bytes[i] as char);
+                i += 1;
+                end = i;
+                continue;
+            }
+            // Up to two separators in a row, as in `(555) 123`
+            let gap = bytes[i..].iter().take(3).take_while(|b| separators.as_bytes().contains(b)).count();
+            if gap == 0 || gap > 2 || !bytes.get(i + gap).is_some_and(u8::is_ascii_digit) {
+                break;
+            }
+            i += gap;
+        }
+        if !text[end..].chars().next().is_some_and(char::is_alphanumeric) {
+            runs.push((start..end, digits));
+        }
+    }
+    runs
+}
+
+// 13 to 19 digits, optionally grouped with spaces or hyphens, passing Luhn
+fn find_card_numbers(text: &str) -> Vec<Range<usize>> {
+    digit_runs(text, " -")
+        .into_iter()
+        .filter(|(_, digits)| (13..=19).contains(&digits.len()) && luhn_valid(digits))
+        .map(|(range, _)| range)
+        .collect()
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let digit = u32::from(b - b'0');
+            match (i % 2 == 1, digit * 2) {
+                (true, doubled) if doubled > 9 => doubled - 9,
+                (true, doubled) => doubled,
+                (false, _) => digit,
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// International numbers with a leading `+` and 8 to 15 digits, or national
+// ones of 10 to 15 digits that are grouped, as in `(555) 123-4567`, or follow
+// a `tel:` label. Shorter runs are too easily dates or amounts, and ungrouped
+// ones order numbers, timestamps or tracking IDs
+fn find_phone_numbers(text: &str) -> Vec<Range<usize>> {
+    let mut phones = Vec::new();
+    for (range, digits) in digit_runs(text, " -.()") {
+        let before = &text[..range.start];
+        // A parenthesized area code opens before the first digit
+        let start = if before.ends_with('(') { range.start - 1 } else { range.start };
+        let international = text[..start].ends_with('+');
+        let found = if international {
+            (8..=15).contains(&digits.len())
+        } else {
+            let grouped = range.end - start > digits.len();
+            let before = text[..start].trim_end();
+            let labelled = before.get(before.len().saturating_sub(4)..).is_some_and(|l| l.eq_ignore_ascii_case("tel:"));
+            (10..=15).contains(&digits.len()) && (grouped || labelled)
+        };
+        if found {
+            phones.push(if international { start - 1 } else { start }..range.end);
+        }
+    }
+    phones
+}
+
+// Two letters, two check digits, then up to 30 letters and digits, optionally
+// in space-separated groups, passing the ISO 13616 mod-97 check
+fn find_ibans(text: &str) -> Vec<Range<usize>> {
+    let mut ibans = Vec::new();
+    let mut search_from = 0;
+    for (start, _) in text.char_indices() {
+        let at_word_start = !text[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        if start < search_from || !at_word_start || !looks_like_iban_start(&text[start..]) {
+            continue;
+        }
+
+        // Candidate ends at each group boundary, tried longest first so a
+        // following word that happens to be alphanumeric is not swallowed
+        let mut compact = String::new();
+        let mut ends = Vec::new();
+        let mut chars = text[start..].char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            if c.is_ascii_alphanumeric() && compact.len() < 34 {
+                compact.push(c.to_ascii_uppercase());
+                if !chars.peek().is_some_and(|(_, next)| next.is_ascii_alphanumeric()) {
+                    ends.push((start + offset + 1, compact.len()));
+                }
+            } else if c == ' ' && chars.peek().is_some_and(|(_, next)| next.is_ascii_alphanumeric()) {
+                continue;
+            } else {
+                break;
+            }
+        }
+
+        let found = ends.iter().rev().find(|(end, len)| {
+            (15..=34).contains(len)
+                && !text[*end..].chars().next().is_some_and(char::is_alphanumeric)
+                && iban_checksum_valid(&compact[..*len])
+        });
+        if let Some((end, _)) = found {
+            ibans.push(start..*end);
+            search_from = *end;
+        }
+    }
+    ibans
+}
+
+fn looks_like_iban_start(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 4
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..4].iter().all(u8::is_ascii_digit)
+}
+
+fn iban_checksum_valid(iban: &str) -> bool {
+    // The first four characters move to the end and letters become 10..=35
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        remainder = if value < 10 { (remainder * 10 + value) % 97 } else { (remainder * 100 + value) % 97 };
+    }
+    remainder == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redacted(text: &str) -> String {
+        redact(text).text
+    }
+
+    #[test]
+    fn test_emails() {
+        assert_eq!(redacted("Mail jane.doe+shop@mail.example.co.uk."), "Mail [EMAIL].");
+        assert_eq!(redacted("me@localhost and @handle and a@b.c"), "me@localhost and @handle and a@b.c");
+    }
+
+    #[test]
+    fn test_card_numbers_need_luhn() {
+        assert_eq!(redacted("Card 4111 1111 1111 1111 was charged"), "Card [CARD] was charged");
+        assert_eq!(redacted("Card 4111-1111-1111-1112 was charged"), "Card 4111-1111-1111-1112 was charged");
+        assert_eq!(redacted("Order 4111111111111111X"), "Order 4111111111111111X");
+    }
+
+    #[test]
+    fn test_ibans_need_checksum() {
+        assert_eq!(redacted("Refund to DE89 3704 0044 0532 0130 00 please"), "Refund to [IBAN] please");
+        assert_eq!(redacted("IBAN GB82WEST12345698765432."), "IBAN [IBAN].");
+        assert_eq!(redacted("Refund to DE89 3704 0044 0532 0130 02 please"), "Refund to DE89 3704 0044 0532 0130 02 please");
+    }
+
+    #[test]
+    fn test_phone_numbers() {
+        assert_eq!(redacted("Call +44 20 7946 0958 or (555) 123-4567."), "Call [PHONE] or [PHONE].");
+        // Dates, years and short numbers are not phone numbers
+        assert_eq!(redacted("On 2024-01-15 I paid 1.299 for order 55512"), "On 2024-01-15 I paid 1.299 for order 55512");
+        // Nor are long ungrouped runs, unless labelled
+        assert_eq!(redacted("order 1234567890123 shipped at 1718000000"), "order 1234567890123 shipped at 1718000000");
+        assert_eq!(redacted("Tel: 5551234567, +4930901820"), "Tel: [PHONE], [PHONE]");
+    }
+
+    #[test]
+    fn test_counts_and_placeholders() {
+        let result = redact("a@example.com, b@example.org, 4111111111111111");
+        assert_eq!(result.text, "[EMAIL], [EMAIL], [CARD]");
+        assert_eq!(result.counts, BTreeMap::from([(PiiKind::Email, 2), (PiiKind::CreditCard, 1)]));
+        assert!(is_placeholder("[CARD]"));
+        assert!(!is_placeholder("CARD"));
+    }
+
+    #[test]
+    fn test_offsets_map_back_around_placeholders() {
+        let text = "Mail a@example.com about the refund";
+        let result = redact(text);
+        let refund = result.text.find("refund").unwrap();
+        assert_eq!(&text[result.offsets.original_range(refund..refund + 6)], "refund");
+        assert_eq!(&text[result.offsets.original_range(5..12)], "a@example.com");
+    }
+}
@@ -0,0 +1,109 @@
+//! Retry policy for transient backend failures: capped exponential backoff
+//! with optional full jitter, deferring to the backend's retry-after hints.
+
+use std::time::Duration;
+
+use crate::error::AnalysisError;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Backend calls per analysis, including the first; 1 disables retries.
+    pub max_attempts: u32,
+    /// Backoff before the first retry; it doubles for each retry after that.
+    pub base_delay: Duration,
+    /// Longest wait between attempts. A retry-after hint longer than this
+    /// ends the retries rather than stalling the caller.
+    pub max_delay: Duration,
+    /// Wait a uniformly random time up to the backoff ("full jitter"), so
+    /// callers that failed together do not retry together.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// How long to wait before another attempt after `attempt` (1-based; 0 is
+    /// treated as 1) failed with `error`, or `None` if it should not be
+    /// retried: the error is not retryable, attempts are used up, or the
+    /// backend asked for a longer wait than `max_delay`.
+    pub fn delay_before_retry(&self, attempt: u32, error: &AnalysisError) -> Option<Duration> {
+        if !error.is_retryable() || attempt >= self.max_attempts {
+            return None;
+        }
+
+        let doubling = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self.base_delay.saturating_mul(doubling).min(self.max_delay);
+        let backoff = if self.jitter {
+            Duration::from_nanos(fastrand::u64(0..=backoff.as_nanos() as u64))
+        } else {
+            backoff
+        };
+
+        match error.retry_after() {
+            Some(hint) if hint > self.max_delay => None,
+            Some(hint) => Some(backoff.max(hint)),
+            None => Some(backoff),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout() -> AnalysisError {
+        AnalysisError::Timeout { after: Duration::from_secs(1) }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 6,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            jitter: false,
+        };
+        let delays: Vec<Option<Duration>> = (1..=6).map(|attempt| policy.delay_before_retry(attempt, &timeout())).collect();
+        let ms = Duration::from_millis;
+        assert_eq!(delays, [Some(ms(100)), Some(ms(200)), Some(ms(400)), Some(ms(500)), Some(ms(500)), None]);
+
+        assert_eq!(policy.delay_before_retry(0, &timeout()), Some(ms(100)));
+        assert_eq!(policy.delay_before_retry(1, &AnalysisError::CircuitBreakerOpen), None);
+        assert_eq!(RetryPolicy::none().delay_before_retry(1, &timeout()), None);
+    }
+
+    #[test]
+    fn test_full_jitter_stays_within_backoff() {
+        let policy = RetryPolicy { max_attempts: 10, ..Default::default() };
+        for attempt in 0..10 {
+            let delay = policy.delay_before_retry(attempt, &timeout()).unwrap();
+            assert!(delay <= policy.max_delay);
+            assert!(delay <= policy.base_delay * 2u32.pow(attempt.saturating_sub(1)));
+        }
+    }
+
+    #[test]
+    fn test_retry_after_hints() {
+        let policy = RetryPolicy { jitter: false, ..Default::default() };
+        let limited = |secs| AnalysisError::RateLimited { retry_after: Some(Duration::from_secs(secs)) };
+        assert_eq!(policy.delay_before_retry(1, &limited(2)), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay_before_retry(1, &limited(60)), None);
+
+        let unhinted = AnalysisError::RateLimited { retry_after: None };
+        assert_eq!(policy.delay_before_retry(1, &unhinted), Some(policy.base_delay));
+    }
+}
@@ -0,0 +1,389 @@
+//! Input sanitization policy: which characters survive, how text is
+//! normalized, and how the size limit is measured.
+
+use std::fmt;
+use std::str::FromStr;
+
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::UnicodeNormalization;
+use unicode_properties::{GeneralCategory, GeneralCategoryGroup, UnicodeEmoji, UnicodeGeneralCategory};
+
+use crate::error::AnalysisError;
+use crate::text::{tokenize, OffsetMap};
+
+/// Unicode normalization applied after filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    None,
+    /// Canonical composition: visually identical text compares equal.
+    Nfc,
+    /// Compatibility composition: also folds ligatures, full-width forms, etc.
+    Nfkc,
+}
+
+/// Unit in which `SanitizationPolicy::max_input` is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitUnit {
+    Bytes,
+    Chars,
+    /// Words as split by `text::tokenize`.
+    Tokens,
+}
+
+impl LimitUnit {
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            LimitUnit::Bytes => text.len(),
+            LimitUnit::Chars => text.chars().count(),
+            LimitUnit::Tokens => tokenize(text).len(),
+        }
+    }
+}
+
+impl fmt::Display for LimitUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            LimitUnit::Bytes => "bytes",
+            LimitUnit::Chars => "chars",
+            LimitUnit::Tokens => "tokens",
+        })
+    }
+}
+
+impl FromStr for LimitUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bytes" => Ok(LimitUnit::Bytes),
+            "chars" => Ok(LimitUnit::Chars),
+            "tokens" => Ok(LimitUnit::Tokens),
+            _ => Err(format!("unknown limit unit `{}` (expected bytes, chars or tokens)", s)),
+        }
+    }
+}
+
+impl FromStr for Normalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Normalization::None),
+            "nfc" => Ok(Normalization::Nfc),
+            "nfkc" => Ok(Normalization::Nfkc),
+            _ => Err(format!("unknown normalization `{}` (expected none, nfc or nfkc)", s)),
+        }
+    }
+}
+
+/// Character classes a policy lets through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharClasses {
+    /// Letters and combining marks.
+    pub letters: bool,
+    pub numbers: bool,
+    pub whitespace: bool,
+    /// Quotes, dashes, brackets and the `:/?#@&%` that make up URLs.
+    pub punctuation: bool,
+    /// Currency, math and other non-emoji symbols.
+    pub symbols: bool,
+    /// Emoji, including the joiners and variation selectors of emoji sequences.
+    pub emoji: bool,
+}
+
+impl CharClasses {
+    pub fn all() -> Self {
+        Self { letters: true, numbers: true, whitespace: true, punctuation: true, symbols: true, emoji: true }
+    }
+
+    fn allows(&self, c: char) -> bool {
+        if is_emoji_part(c) {
+            return self.emoji;
+        }
+        if c.is_whitespace() {
+            return self.whitespace;
+        }
+        match c.general_category_group() {
+            GeneralCategoryGroup::Letter | GeneralCategoryGroup::Mark => self.letters,
+            GeneralCategoryGroup::Number => self.numbers,
+            GeneralCategoryGroup::Punctuation => self.punctuation,
+            GeneralCategoryGroup::Symbol => self.symbols,
+            GeneralCategoryGroup::Separator | GeneralCategoryGroup::Other => false,
+        }
+    }
+}
+
+/// Why a character was dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalReason {
+    /// A control or invisible formatting character (e.g. bidi overrides).
+    Control,
+    /// Outside the policy's allowed classes.
+    Disallowed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Removal {
+    /// Byte offset of the character in the original input.
+    pub offset: usize,
+    pub character: char,
+    pub reason: RemovalReason,
+}
+
+/// What sanitization changed, for callers that need to audit it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizationReport {
+    pub removed: Vec<Removal>,
+    /// Whether Unicode normalization changed the filtered text.
+    pub normalized: bool,
+}
+
+impl SanitizationReport {
+    pub fn is_unchanged(&self) -> bool {
+        self.removed.is_empty() && !self.normalized
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sanitized {
+    pub text: String,
+    pub report: SanitizationReport,
+    /// Maps byte offsets in `text` back to the input.
+    pub offsets: OffsetMap,
+}
+
+#[derive(Debug, Clone)]
+pub struct SanitizationPolicy {
+    pub allowed: CharClasses,
+    /// Characters allowed regardless of their class.
+    pub extra_allowed: String,
+    /// Drop control and invisible formatting characters other than whitespace.
+    pub strip_control: bool,
+    pub normalization: Normalization,
+    pub max_input: usize,
+    pub limit_unit: LimitUnit,
+}
+
+impl Default for SanitizationPolicy {
+    /// Keeps all visible text, including emoji, currency and URLs; strips
+    /// control characters, applies NFC and limits input to 10,000 characters.
+    fn default() -> Self {
+        Self {
+            allowed: CharClasses::all(),
+            extra_allowed: String::new(),
+            strip_control: true,
+            normalization: Normalization::Nfc,
+            max_input: 10_000,
+            limit_unit: LimitUnit::Chars,
+        }
+    }
+}
+
+impl SanitizationPolicy {
+    /// The original filter: alphanumerics, whitespace and `.,!?'-` only,
+    /// limited to 10,000 bytes, without normalization.
+    pub fn strict() -> Self {
+        Self {
+            allowed: CharClasses {
+                letters: true,
+                numbers: true,
+                whitespace: true,
+                punctuation: false,
+                symbols: false,
+                emoji: false,
+            },
+            extra_allowed: ".,!?'-".to_string(),
+            strip_control: true,
+            normalization: Normalization::None,
+            max_input: 10_000,
+            limit_unit: LimitUnit::Bytes,
+        }
+    }
+
+    pub fn apply(&self, input: &str) -> Result<Sanitized, AnalysisError> {
+        if input.is_empty() {
+            return Err(AnalysisError::EmptyInput);
+        }
+
+        let size = self.limit_unit.measure(input);
+        if size > self.max_input {
+            return Err(AnalysisError::InputTooLarge {
+                limit: self.max_input,
+                actual: size,
+                unit: self.limit_unit,
+            });
+        }
+
+        let mut report = SanitizationReport::default();
+        let mut filtered = String::with_capacity(input.len());
+        let mut offsets = OffsetMap::default();
+        for (offset, c) in input.char_indices() {
+            let reason = if self.extra_allowed.contains(c) {
+                None
+            } else if is_control(c) {
+                self.strip_control.then_some(RemovalReason::Control)
+            } else if !self.allowed.allows(c) {
+                Some(RemovalReason::Disallowed)
+            } else {
+                None
+            };
+
+            match reason {
+                Some(reason) => report.removed.push(Removal { offset, character: c, reason }),
+                None => {
+                    offsets.push(filtered.len()..filtered.len() + c.len_utf8(), offset..offset + c.len_utf8());
+                    filtered.push(c);
+                }
+            }
+        }
+
+        let normalized: Option<String> = match self.normalization {
+            Normalization::None => None,
+            Normalization::Nfc => Some(filtered.nfc().collect()),
+            Normalization::Nfkc => Some(filtered.nfkc().collect()),
+        };
+        let text = match normalized {
+            Some(normalized) if normalized != filtered => {
+                report.normalized = true;
+                offsets = self.normalized_offsets(&filtered, &normalized, &offsets);
+                normalized
+            }
+            _ => filtered,
+        };
+
+        if text.is_empty() {
+            return Err(AnalysisError::EmptyInput);
+        }
+
+        Ok(Sanitized { text, report, offsets })
+    }
+
+    // Maps `normalized` back to the input by normalizing each starter with the
+    // marks after it on its own. That gives the same text as normalizing the
+    // whole except where starters compose with each other, as Hangul jamo do;
+    // such text maps only as a whole
+    fn normalized_offsets(&self, filtered: &str, normalized: &str, filtered_offsets: &OffsetMap) -> OffsetMap {
+        let mut starts: Vec<usize> = filtered
+            .char_indices()
+            .filter(|(_, c)| canonical_combining_class(*c) == 0)
+            .map(|(offset, _)| offset)
+            .collect();
+        if starts.first() != Some(&0) {
+            starts.insert(0, 0);
+        }
+        starts.push(filtered.len());
+
+        let mut text = String::with_capacity(normalized.len());
+        let mut offsets = OffsetMap::default();
+        for run in starts.windows(2) {
+            let run = run[0]..run[1];
+            let start = text.len();
+            match self.normalization {
+                Normalization::None => text.push_str(&filtered[run.clone()]),
+                Normalization::Nfc => text.extend(filtered[run.clone()].nfc()),
+                Normalization::Nfkc => text.extend(filtered[run.clone()].nfkc()),
+            }
+            offsets.push(start..text.len(), filtered_offsets.original_range(run));
+        }
+
+        if text == normalized {
+            offsets
+        } else {
+            let mut offsets = OffsetMap::default();
+            offsets.push(0..normalized.len(), filtered_offsets.original_range(0..filtered.len()));
+            offsets
+        }
+    }
+}
+
+// Control characters and invisible formatting, but not ordinary whitespace
+// or the zero-width joiner that glues emoji sequences together
+fn is_control(c: char) -> bool {
+    !c.is_whitespace()
+        && !is_emoji_part(c)
+        && matches!(
+            c.general_category(),
+            GeneralCategory::Control | GeneralCategory::Format | GeneralCategory::PrivateUse
+                | GeneralCategory::Surrogate | GeneralCategory::Unassigned
+        )
+}
+
+fn is_emoji_part(c: char) -> bool {
+    // Digits, '#' and '*' are emoji "characters" too, but only as keycap bases
+    (c.is_emoji_char() && !c.is_ascii()) || c == '\u{200D}' || ('\u{FE00}'..='\u{FE0F}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_keeps_meaningful_symbols() {
+        let input = "Loved it 😍 — only €20! See https://example.com/p?id=1 \"wow\"";
+        let sanitized = SanitizationPolicy::default().apply(input).unwrap();
+        assert_eq!(sanitized.text, input);
+        assert!(sanitized.report.is_unchanged());
+    }
+
+    #[test]
+    fn test_strict_matches_original_filter() {
+        let sanitized = SanitizationPolicy::strict().apply("Great 👍 price: $5!").unwrap();
+        assert_eq!(sanitized.text, "Great  price 5!");
+
+        let removed: Vec<(usize, char)> =
+            sanitized.report.removed.iter().map(|r| (r.offset, r.character)).collect();
+        assert_eq!(removed, [(6, '👍'), (16, ':'), (18, '$')]);
+        assert!(sanitized.report.removed.iter().all(|r| r.reason == RemovalReason::Disallowed));
+    }
+
+    #[test]
+    fn test_strips_controls_and_keeps_emoji_sequences() {
+        let input = "ok\u{7}\u{202E} 👩\u{200D}💻\tdone\n";
+        let sanitized = SanitizationPolicy::default().apply(input).unwrap();
+        assert_eq!(sanitized.text, "ok 👩\u{200D}💻\tdone\n");
+        assert_eq!(sanitized.report.removed.len(), 2);
+        assert!(sanitized.report.removed.iter().all(|r| r.reason == RemovalReason::Control));
+        assert!(!sanitized.report.normalized);
+    }
+
+    #[test]
+    fn test_normalization() {
+        let decomposed = "cafe\u{301} \u{FB01}ne";
+        let nfc = SanitizationPolicy::default().apply(decomposed).unwrap();
+        assert_eq!(nfc.text, "café \u{FB01}ne");
+        assert!(nfc.report.normalized);
+
+        let policy = SanitizationPolicy { normalization: Normalization::Nfkc, ..Default::default() };
+        assert_eq!(policy.apply(decomposed).unwrap().text, "café fine");
+    }
+
+    #[test]
+    fn test_offsets_map_back_to_input() {
+        let input = "\u{202E}cafe\u{301} \u{FB01}ne wins";
+        let policy = SanitizationPolicy { normalization: Normalization::Nfkc, ..Default::default() };
+        let sanitized = policy.apply(input).unwrap();
+        assert_eq!(sanitized.text, "café fine wins");
+
+        let original = |word: &str| {
+            let start = sanitized.text.find(word).unwrap();
+            let range = sanitized.offsets.original_range(start..start + word.len());
+            &input[range]
+        };
+        assert_eq!(original("café"), "cafe\u{301}");
+        assert_eq!(original("fine"), "\u{FB01}ne");
+        assert_eq!(original("wins"), "wins");
+    }
+
+    #[test]
+    fn test_limit_units() {
+        let text = "héllo wörld";
+        for (unit, size) in [(LimitUnit::Bytes, 13), (LimitUnit::Chars, 11), (LimitUnit::Tokens, 2)] {
+            let policy = SanitizationPolicy { max_input: size - 1, limit_unit: unit, ..Default::default() };
+            assert!(matches!(
+                policy.apply(text),
+                Err(AnalysisError::InputTooLarge { limit, actual, unit: u })
+                    if limit == size - 1 && actual == size && u == unit
+            ));
+        }
+    }
+}
@@ -0,0 +1,395 @@
+//! Offline, lexicon-based sentiment scoring in the style of VADER.
+//!
+//! Each word found in the lexicon contributes its valence, adjusted by booster
+//! words and negations in the three preceding words, by a contrastive "but",
+//! and by ALL-CAPS emphasis. Unmatched words count towards the neutral share,
+//! so the three proportions always sum to 1.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+
+use crate::analysis::SentimentScore;
+use crate::language::Language;
+use crate::text::tokenize;
+
+const ENGLISH_LEXICON: &str = include_str!("../data/lexicon_en.tsv");
+const SPANISH_LEXICON: &str = include_str!("../data/lexicon_es.tsv");
+const GERMAN_LEXICON: &str = include_str!("../data/lexicon_de.tsv");
+
+const ENGLISH_NEGATIONS: &[&str] = &[
+    "not", "no", "never", "none", "nobody", "nothing", "neither", "nor", "nowhere",
+    "without", "cannot", "hardly", "rarely", "seldom", "cant", "dont", "wont", "isnt",
+];
+
+const ENGLISH_BOOSTERS: &[(&str, f64)] = &[
+    ("absolutely", BOOST), ("completely", BOOST), ("deeply", BOOST), ("especially", BOOST),
+    ("extremely", BOOST), ("highly", BOOST), ("incredibly", BOOST), ("really", BOOST),
+    ("so", BOOST), ("super", BOOST), ("totally", BOOST), ("truly", BOOST), ("very", BOOST),
+    ("barely", -BOOST), ("marginally", -BOOST), ("partly", -BOOST), ("slightly", -BOOST),
+    ("somewhat", -BOOST), ("occasionally", -BOOST),
+];
+
+const SPANISH_NEGATIONS: &[&str] = &[
+    "no", "nunca", "jamás", "nada", "nadie", "ni", "ningún", "ninguno", "ninguna", "tampoco", "sin",
+];
+
+const SPANISH_BOOSTERS: &[(&str, f64)] = &[
+    ("absolutamente", BOOST), ("completamente", BOOST), ("increíblemente", BOOST), ("muy", BOOST),
+    ("realmente", BOOST), ("súper", BOOST), ("tan", BOOST), ("totalmente", BOOST),
+    ("algo", -BOOST), ("apenas", -BOOST), ("ligeramente", -BOOST), ("poco", -BOOST),
+];
+
+const GERMAN_NEGATIONS: &[&str] = &[
+    "nicht", "kein", "keine", "keinen", "keinem", "keiner", "keines", "nie", "niemals", "nichts",
+    "ohne", "weder",
+];
+
+const GERMAN_BOOSTERS: &[(&str, f64)] = &[
+    ("absolut", BOOST), ("äußerst", BOOST), ("besonders", BOOST), ("echt", BOOST),
+    ("extrem", BOOST), ("sehr", BOOST), ("so", BOOST), ("total", BOOST), ("wirklich", BOOST),
+    ("etwas", -BOOST), ("kaum", -BOOST), ("leicht", -BOOST), ("wenig", -BOOST),
+];
+
+/// Valence added (or removed) by a booster directly before a sentiment word.
+const BOOST: f64 = 0.293;
+/// Extra valence for a word shouted in ALL CAPS among lower-case text.
+const CAPS_BOOST: f64 = 0.733;
+/// Multiplier applied to a sentiment word inside a negation window.
+const NEGATION_SCALAR: f64 = -0.74;
+/// How many preceding words a booster or negation reaches.
+const WINDOW: usize = 3;
+/// Emphasis added per exclamation mark, up to four of them.
+const EXCLAMATION_BOOST: f64 = 0.292;
+/// Normalization constant for the compound score.
+const COMPOUND_ALPHA: f64 = 15.0;
+
+/// The bundled negation words for `language`; none for a language without a
+/// bundled lexicon.
+pub(crate) fn bundled_negations(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::English => ENGLISH_NEGATIONS,
+        Language::Spanish => SPANISH_NEGATIONS,
+        Language::German => GERMAN_NEGATIONS,
+        Language::French | Language::Portuguese => &[],
+    }
+}
+
+/// Word valences plus the negation, booster and contrastive words that modify them.
+#[derive(Debug, Clone)]
+pub struct Lexicon {
+    valences: HashMap<String, f64>,
+    negations: HashSet<String>,
+    boosters: HashMap<String, f64>,
+    /// Words like "but" that shift weight from the clause before to the one after.
+    contrastives: HashSet<String>,
+}
+
+/// The result of scoring one text, with the evidence behind it.
+#[derive(Debug)]
+pub struct LexiconScore {
+    pub sentiment: SentimentScore,
+    /// Overall polarity in [-1, 1].
+    pub compound: f64,
+    /// Words that carried sentiment.
+    pub matched_tokens: usize,
+    pub total_tokens: usize,
+}
+
+impl Lexicon {
+    /// The bundled English lexicon with English negations and boosters.
+    pub fn english() -> Self {
+        Self::parse(ENGLISH_LEXICON).expect("bundled lexicon is valid")
+    }
+
+    /// The bundled Spanish lexicon with Spanish negations and boosters.
+    pub fn spanish() -> Self {
+        Self::parse(SPANISH_LEXICON)
+            .expect("bundled lexicon is valid")
+            .with_negations(SPANISH_NEGATIONS.iter().map(|w| w.to_string()))
+            .with_boosters(SPANISH_BOOSTERS.iter().map(|(w, b)| (w.to_string(), *b)))
+            .with_contrastives(["pero".to_string()])
+    }
+
+    /// The bundled German lexicon with German negations and boosters.
+    pub fn german() -> Self {
+        Self::parse(GERMAN_LEXICON)
+            .expect("bundled lexicon is valid")
+            .with_negations(GERMAN_NEGATIONS.iter().map(|w| w.to_string()))
+            .with_boosters(GERMAN_BOOSTERS.iter().map(|(w, b)| (w.to_string(), *b)))
+            .with_contrastives(["aber".to_string()])
+    }
+
+    /// The bundled lexicon for `language`, if there is one.
+    pub fn bundled(language: Language) -> Option<Self> {
+        match language {
+            Language::English => Some(Self::english()),
+            Language::Spanish => Some(Self::spanish()),
+            Language::German => Some(Self::german()),
+            Language::French | Language::Portuguese => None,
+        }
+    }
+
+    /// Loads a VADER-style lexicon file (`token<TAB>valence[<TAB>...]`).
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses lexicon lines; blank lines and `#` comments are skipped.
+    /// Negations, boosters and contrastives default to the English lists.
+    pub fn parse(source: &str) -> io::Result<Self> {
+        let mut valences = HashMap::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim_end();
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = line.split('\t');
+            let token = columns.next().unwrap_or_default().trim();
+            let valence = columns
+                .next()
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("lexicon line {}: expected `token<TAB>valence`", number + 1),
+                    )
+                })?;
+            valences.insert(token.to_lowercase(), valence);
+        }
+
+        Ok(Self {
+            valences,
+            negations: ENGLISH_NEGATIONS.iter().map(|w| w.to_string()).collect(),
+            boosters: ENGLISH_BOOSTERS.iter().map(|(w, b)| (w.to_string(), *b)).collect(),
+            contrastives: HashSet::from(["but".to_string()]),
+        })
+    }
+
+    pub fn with_negations<I: IntoIterator<Item = String>>(mut self, negations: I) -> Self {
+        self.negations = negations.into_iter().collect();
+        self
+    }
+
+    pub fn with_boosters<I: IntoIterator<Item = (String, f64)>>(mut self, boosters: I) -> Self {
+        self.boosters = boosters.into_iter().collect();
+        self
+    }
+
+    pub fn with_contrastives<I: IntoIterator<Item = String>>(mut self, contrastives: I) -> Self {
+        self.contrastives = contrastives.into_iter().collect();
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.valences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.valences.is_empty()
+    }
+
+    /// Every word the lexicon reacts to: valence words, negations and boosters.
+    pub fn vocabulary(&self) -> impl Iterator<Item = &str> {
+        self.valences
+            .keys()
+            .chain(&self.negations)
+            .chain(self.boosters.keys())
+            .map(String::as_str)
+    }
+
+    pub fn valence(&self, word: &str) -> Option<f64> {
+        self.valences.get(word).copied()
+    }
+
+    fn is_negation(&self, word: &str) -> bool {
+        self.negations.contains(word) || word.ends_with("n't") || word.ends_with("n\u{2019}t")
+    }
+
+    pub fn score(&self, text: &str) -> LexiconScore {
+        let tokens = tokenize(text);
+        let words: Vec<String> = tokens.iter().map(|t| t.lowercase()).collect();
+
+        let is_shouted = |word: &str| {
+            word.chars().count() > 1
+                && word.chars().any(char::is_alphabetic)
+                && !word.chars().any(char::is_lowercase)
+        };
+        let shouted = tokens.iter().filter(|t| is_shouted(t.text)).count();
+        let caps_differ = shouted > 0 && shouted < tokens.len();
+        let but_index = words.iter().position(|w| self.contrastives.contains(w));
+
+        let mut valences = Vec::with_capacity(words.len());
+        for (i, word) in words.iter().enumerate() {
+            let Some(mut valence) = self.valence(word) else {
+                valences.push(0.0);
+                continue;
+            };
+
+            if caps_differ && is_shouted(tokens[i].text) {
+                valence += CAPS_BOOST * valence.signum();
+            }
+
+            for distance in 1..=WINDOW.min(i) {
+                if let Some(boost) = self.boosters.get(&words[i - distance]) {
+                    let decay = match distance {
+                        1 => 1.0,
+                        2 => 0.95,
+                        _ => 0.9,
+                    };
+                    valence += boost * decay * valence.signum();
+                }
+            }
+
+            if (1..=WINDOW.min(i)).any(|distance| self.is_negation(&words[i - distance])) {
+                valence *= NEGATION_SCALAR;
+            }
+
+            match but_index {
+                Some(but) if i < but => valence *= 0.5,
+                Some(but) if i > but => valence *= 1.5,
+                _ => {}
+            }
+
+            valences.push(valence);
+        }
+
+        let emphasis = text.matches('!').count().min(4) as f64 * EXCLAMATION_BOOST;
+        let matched_tokens = valences.iter().filter(|v| **v != 0.0).count();
+
+        // Fold from +0.0: an empty f64 `sum()` yields -0.0, which leaks into output
+        let mut sum = valences.iter().fold(0.0, |acc, v| acc + v);
+        if sum != 0.0 {
+            sum += emphasis * sum.signum();
+        }
+        let compound = sum / (sum * sum + COMPOUND_ALPHA).sqrt();
+
+        let mut positive = valences.iter().filter(|v| **v > 0.0).fold(0.0, |acc, v| acc + v + 1.0);
+        let mut negative = valences.iter().filter(|v| **v < 0.0).fold(0.0, |acc, v| acc + 1.0 - v);
+        let neutral = valences.iter().filter(|v| **v == 0.0).count() as f64;
+        if positive > negative {
+            positive += emphasis;
+        } else if negative > positive {
+            negative += emphasis;
+        }
+
+        let total = positive + negative + neutral;
+        let sentiment = if total > 0.0 {
+            SentimentScore {
+                positive: positive / total,
+                negative: negative / total,
+                neutral: neutral / total,
+            }
+        } else {
+            SentimentScore { positive: 0.0, negative: 0.0, neutral: 1.0 }
+        };
+
+        LexiconScore {
+            sentiment,
+            compound,
+            matched_tokens,
+            total_tokens: tokens.len(),
+        }
+    }
+}
+
+impl Default for Lexicon {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+// Entries are hashed in sorted order so equal lexicons hash equally
+impl Hash for Lexicon {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut valences: Vec<_> = self.valences.iter().map(|(w, v)| (w, v.to_bits())).collect();
+        valences.sort_unstable();
+        valences.hash(state);
+
+        let mut negations: Vec<_> = self.negations.iter().collect();
+        negations.sort_unstable();
+        negations.hash(state);
+
+        let mut boosters: Vec<_> = self.boosters.iter().map(|(w, v)| (w, v.to_bits())).collect();
+        boosters.sort_unstable();
+        boosters.hash(state);
+
+        let mut contrastives: Vec<_> = self.contrastives.iter().collect();
+        contrastives.sort_unstable();
+        contrastives.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon() -> Lexicon {
+        Lexicon::parse("great\t3.1\nlove\t3.2\nterrible\t-2.1\nslow\t-1.1\t0.7\t[-1, -1]\n").unwrap()
+    }
+
+    #[test]
+    fn test_scores_are_normalized() {
+        let lexicon = Lexicon::english();
+        for text in ["I love this", "This is terrible and slow", "The box arrived", ""] {
+            let s = lexicon.score(text).sentiment;
+            assert!((s.positive + s.negative + s.neutral - 1.0).abs() < 1e-9, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_whole_words_only() {
+        let lexicon = lexicon();
+        let score = lexicon.score("The product felt unloved");
+        assert_eq!(score.matched_tokens, 0);
+        assert_eq!(score.sentiment.neutral, 1.0);
+    }
+
+    #[test]
+    fn test_negation_flips_polarity() {
+        let lexicon = lexicon();
+        assert!(lexicon.score("This is great").compound > 0.0);
+        assert!(lexicon.score("This is not great").compound < 0.0);
+        assert!(lexicon.score("It wasn't very great").compound < 0.0);
+    }
+
+    #[test]
+    fn test_boosters_and_emphasis() {
+        let lexicon = lexicon();
+        let plain = lexicon.score("the service is great").compound;
+        assert!(lexicon.score("the service is really great").compound > plain);
+        assert!(lexicon.score("the service is slightly great").compound < plain);
+        assert!(lexicon.score("the service is GREAT").compound > plain);
+        assert!(lexicon.score("the service is great!!").compound > plain);
+    }
+
+    #[test]
+    fn test_but_shifts_weight_to_second_clause() {
+        let lexicon = lexicon();
+        assert!(lexicon.score("I love the design but shipping was terrible").compound < 0.0);
+    }
+
+    #[test]
+    fn test_rejects_malformed_lines() {
+        let err = Lexicon::parse("# comment\ngood\t1.9\nbroken line\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 3"));
+    }
+
+    #[test]
+    fn test_bundled_spanish_and_german() {
+        let spanish = Lexicon::bundled(Language::Spanish).unwrap();
+        assert!(spanish.score("El producto es muy bueno").compound > 0.0);
+        assert!(spanish.score("El producto no es bueno").compound < 0.0);
+        assert!(spanish.score("Bonito diseño pero la entrega fue un desastre").compound < 0.0);
+
+        let german = Lexicon::bundled(Language::German).unwrap();
+        assert!(german.score("Das Essen war wirklich lecker").compound > 0.0);
+        assert!(german.score("Das Essen war nicht gut").compound < 0.0);
+
+        assert!(Lexicon::bundled(Language::French).is_none());
+    }
+}
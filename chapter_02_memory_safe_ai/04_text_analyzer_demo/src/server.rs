@@ -0,0 +1,302 @@
+//! HTTP service mode: `POST /analyze`, `POST /analyze/batch`, `GET /health`
+//! and Prometheus metrics on `GET /metrics`.
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::Arc;
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+use crate::analysis::TextAnalysis;
+use crate::analyzer::TextAnalyzer;
+use crate::cache::CacheStats;
+use crate::circuit_breaker::BreakerState;
+use crate::error::AnalysisError;
+use crate::output::{error_chain, AnalysisRecord, SCHEMA_VERSION};
+use crate::sanitize::LimitUnit;
+
+/// Default cap on request bodies, in bytes.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+#[derive(Clone)]
+struct AppState {
+    analyzer: Arc<TextAnalyzer>,
+    max_body_bytes: usize,
+}
+
+#[derive(Deserialize)]
+struct AnalyzeRequest {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct BatchRequest {
+    texts: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct BatchResponse<'a> {
+    schema_version: u32,
+    results: Vec<AnalysisRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    circuit_breaker: BreakerState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache: Option<CacheStats>,
+}
+
+/// Errors surfaced to HTTP clients as `{"error": ..., "kind": ...}`.
+enum ServiceError {
+    Analysis(AnalysisError),
+    BadRequest(String),
+}
+
+impl From<AnalysisError> for ServiceError {
+    fn from(error: AnalysisError) -> Self {
+        ServiceError::Analysis(error)
+    }
+}
+
+impl IntoResponse for ServiceError {
+    fn into_response(self) -> Response {
+        let (status, kind, message) = match &self {
+            ServiceError::Analysis(e) => (status_for(e), e.kind(), error_chain(e)),
+            ServiceError::BadRequest(message) => {
+                (StatusCode::BAD_REQUEST, "invalid_request", message.clone())
+            }
+        };
+
+        let mut response =
+            (status, Json(serde_json::json!({ "error": message, "kind": kind }))).into_response();
+        if let ServiceError::Analysis(e) = &self {
+            if let Some(wait) = e.retry_after() {
+                response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(wait.as_secs().max(1)));
+            }
+        }
+        response
+    }
+}
+
+fn status_for(error: &AnalysisError) -> StatusCode {
+    match error {
+        AnalysisError::EmptyInput => StatusCode::BAD_REQUEST,
+        AnalysisError::InputTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        AnalysisError::UnsupportedLanguage { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        AnalysisError::CircuitBreakerOpen => StatusCode::SERVICE_UNAVAILABLE,
+        AnalysisError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        AnalysisError::Timeout { .. } | AnalysisError::DeadlineExceeded { .. } => StatusCode::GATEWAY_TIMEOUT,
+        AnalysisError::ApiError(_)
+        | AnalysisError::Http { .. }
+        | AnalysisError::Transport(_)
+        | AnalysisError::InvalidResponse { .. } => StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// Routes for the analysis service, with request bodies capped at `max_body_bytes`.
+pub fn router(analyzer: Arc<TextAnalyzer>, max_body_bytes: usize) -> Router {
+    Router::new()
+        .route("/analyze", post(analyze))
+        .route("/analyze/batch", post(analyze_batch))
+        .route("/health", get(health))
+        .route("/metrics", get(metrics))
+        // Bodies are read by `read_json`, which maps oversize requests to InputTooLarge
+        .layer(DefaultBodyLimit::disable())
+        .with_state(AppState { analyzer, max_body_bytes })
+}
+
+/// Serves the analysis API on `addr` until Ctrl-C.
+pub async fn serve(
+    analyzer: Arc<TextAnalyzer>,
+    addr: impl ToSocketAddrs,
+    max_body_bytes: usize,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(listener, router(analyzer, max_body_bytes))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+}
+
+async fn analyze(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<TextAnalysis>, ServiceError> {
+    let request: AnalyzeRequest = read_json(&headers, body, state.max_body_bytes).await?;
+    Ok(Json(state.analyzer.analyze_text(&request.text).await?))
+}
+
+async fn analyze_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, ServiceError> {
+    let request: BatchRequest = read_json(&headers, body, state.max_body_bytes).await?;
+    if state.analyzer.breaker_state() == BreakerState::Open {
+        return Err(AnalysisError::CircuitBreakerOpen.into());
+    }
+
+    let texts: Vec<&str> = request.texts.iter().map(String::as_str).collect();
+    let results = state.analyzer.analyze_batch(&texts).await;
+    let ids: Vec<String> = (0..texts.len()).map(|i| i.to_string()).collect();
+
+    let records = ids
+        .iter()
+        .zip(&texts)
+        .zip(&results)
+        .map(|((id, text), result)| AnalysisRecord::new(id, text, result))
+        .collect();
+    Ok(Json(BatchResponse { schema_version: SCHEMA_VERSION, results: records }).into_response())
+}
+
+async fn health(State(state): State<AppState>) -> Json<HealthResponse> {
+    let circuit_breaker = state.analyzer.breaker_state();
+    let status = match circuit_breaker {
+        BreakerState::Open => "degraded",
+        BreakerState::Closed | BreakerState::HalfOpen => "ok",
+    };
+    Json(HealthResponse { status, circuit_breaker, cache: state.analyzer.cache_stats() })
+}
+
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let body = state.analyzer.metrics().to_prometheus();
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+async fn read_json<T: DeserializeOwned>(
+    headers: &HeaderMap,
+    body: Body,
+    limit: usize,
+) -> Result<T, ServiceError> {
+    let too_large = |actual| AnalysisError::InputTooLarge { limit, actual, unit: LimitUnit::Bytes };
+
+    let declared = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if let Some(length) = declared.filter(|length| *length > limit) {
+        return Err(too_large(length).into());
+    }
+
+    // Without a Content-Length the body is not read past the limit, so the
+    // reported size is only a lower bound
+    let bytes = to_bytes(body, limit).await.map_err(|_| too_large(limit + 1))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| ServiceError::BadRequest(format!("invalid JSON body: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Fault, MockBackend, ScriptStep, ScriptedBackend};
+    use axum::http::Request;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn mock_router(max_body_bytes: usize) -> Router {
+        let backend = MockBackend::with_latency(Duration::ZERO);
+        router(Arc::new(TextAnalyzer::with_backend(Box::new(backend))), max_body_bytes)
+    }
+
+    async fn send(router: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_analyze_endpoint() {
+        let router = mock_router(DEFAULT_MAX_BODY_BYTES);
+
+        let (status, body) = send(&router, "POST", "/analyze", r#"{"text":"I love it"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["sentiment"]["positive"].as_f64().unwrap() > 0.5);
+
+        let (status, body) = send(&router, "POST", "/analyze", r#"{"text":""}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["kind"], "empty_input");
+
+        let (status, body) = send(&router, "POST", "/analyze", "{").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["kind"], "invalid_request");
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_maps_to_input_too_large() {
+        let router = mock_router(16);
+        let (status, body) = send(&router, "POST", "/analyze", r#"{"text":"far too long for the limit"}"#).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["kind"], "input_too_large");
+    }
+
+    #[tokio::test]
+    async fn test_batch_endpoint_preserves_order() {
+        let router = mock_router(DEFAULT_MAX_BODY_BYTES);
+        let (status, body) =
+            send(&router, "POST", "/analyze/batch", r#"{"texts":["I love it","","awful"]}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["schema_version"], SCHEMA_VERSION);
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1]["error"], "Input text is empty");
+        assert!(results[2]["analysis"]["sentiment"]["negative"].as_f64().unwrap() > 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_open_breaker_returns_503_and_degrades_health() {
+        let analyzer = Arc::new(TextAnalyzer::with_backend(Box::new(ScriptedBackend::new([]).then(ScriptStep::fail(Fault::Api)))));
+        let router = router(analyzer, DEFAULT_MAX_BODY_BYTES);
+        for _ in 0..3 {
+            let (status, _) = send(&router, "POST", "/analyze", r#"{"text":"hi"}"#).await;
+            assert_eq!(status, StatusCode::BAD_GATEWAY);
+        }
+
+        let (status, body) = send(&router, "POST", "/analyze", r#"{"text":"hi"}"#).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["kind"], "circuit_breaker_open");
+
+        let (status, _) = send(&router, "POST", "/analyze/batch", r#"{"texts":["hi"]}"#).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let (status, body) = send(&router, "GET", "/health", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["circuit_breaker"], "open");
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let router = mock_router(DEFAULT_MAX_BODY_BYTES);
+        send(&router, "POST", "/analyze", r#"{"text":"I love it"}"#).await;
+        send(&router, "POST", "/analyze", r#"{"text":""}"#).await;
+
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains("analyzer_requests_total 2\n"));
+        assert!(text.contains("analyzer_errors_total{kind=\"empty_input\"} 1\n"));
+        assert!(text.contains("analyzer_backend_latency_seconds_count{backend=\"mock\"} 1\n"));
+    }
+}
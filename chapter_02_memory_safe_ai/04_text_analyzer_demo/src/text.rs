@@ -0,0 +1,253 @@
+//! Tokenization and offset mapping shared by the offline analysis components.
+
+use std::ops::Range;
+
+/// A word in the source text with its byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token<'_> {
+    pub fn lowercase(&self) -> String {
+        self.text.to_lowercase()
+    }
+}
+
+/// Splits `text` into words: runs of alphanumeric characters, keeping
+/// apostrophes inside a word so contractions like "don't" stay whole.
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let inner_apostrophe = is_apostrophe(c)
+            && start.is_some()
+            && chars.peek().is_some_and(|(_, next)| next.is_alphanumeric());
+
+        if c.is_alphanumeric() || inner_apostrophe {
+            start.get_or_insert(index);
+        } else if let Some(begin) = start.take() {
+            tokens.push(Token { text: &text[begin..index], start: begin, end: index });
+        }
+    }
+    if let Some(begin) = start {
+        tokens.push(Token { text: &text[begin..], start: begin, end: text.len() });
+    }
+
+    tokens
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+/// Abbreviations whose trailing period does not end a sentence.
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "approx", "inc", "ltd",
+];
+
+/// A sentence in the source text with its byte offsets, trimmed of
+/// surrounding whitespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sentence<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits `text` into sentences at line breaks and at `.`, `!`, `?` or `…`
+/// followed by whitespace and a word that does not start in lowercase.
+/// Closing quotes and brackets stay with the sentence they end, and periods
+/// after common abbreviations ("e.g.", "Dr.") or initials do not split.
+pub fn sentences(text: &str) -> Vec<Sentence<'_>> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut sentences = Vec::new();
+    let mut start = 0;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let (index, c) = chars[i];
+        if c == '\n' {
+            push_sentence(text, start, index, &mut sentences);
+            start = index + c.len_utf8();
+        } else if is_terminator(c) {
+            // Take the whole run of terminators and closing punctuation
+            let mut j = i + 1;
+            while j < chars.len() && (is_terminator(chars[j].1) || is_closing(chars[j].1)) {
+                j += 1;
+            }
+            let end = chars.get(j).map_or(text.len(), |(index, _)| *index);
+            let next_word = chars[j..].iter().map(|(_, c)| *c).find(|c| !c.is_whitespace());
+            let at_break = chars.get(j).is_none_or(|(_, c)| c.is_whitespace());
+
+            if at_break
+                && !next_word.is_some_and(char::is_lowercase)
+                && !(c == '.' && j == i + 1 && is_abbreviation(&text[start..index]))
+            {
+                push_sentence(text, start, end, &mut sentences);
+                start = end;
+            }
+            i = j;
+            continue;
+        }
+        i += 1;
+    }
+    push_sentence(text, start, text.len(), &mut sentences);
+
+    sentences
+}
+
+fn push_sentence<'a>(text: &'a str, start: usize, end: usize, sentences: &mut Vec<Sentence<'a>>) {
+    let slice = &text[start..end];
+    let trimmed = slice.trim_start();
+    let start = start + (slice.len() - trimmed.len());
+    let trimmed = trimmed.trim_end();
+    if !trimmed.is_empty() {
+        sentences.push(Sentence { text: trimmed, start, end: start + trimmed.len() });
+    }
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '\u{2019}' | '\u{201D}' | '»')
+}
+
+// Whether the word ending `before` (the text up to a period) is an
+// abbreviation or a single-letter initial
+fn is_abbreviation(before: &str) -> bool {
+    let word = before
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or("")
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    let mut letters = word.chars();
+    let initial = letters.next().is_some_and(char::is_alphabetic) && letters.next().is_none();
+    initial || ABBREVIATIONS.contains(&word.as_str())
+}
+
+/// Maps byte offsets in text derived from an original, by sanitization or
+/// redaction, back to offsets in the original.
+///
+/// The derived text is a series of pieces, each made from one range of the
+/// original. A piece of the same length as its original maps byte for byte;
+/// one that changed length, such as a placeholder or a normalized character,
+/// only maps as a whole.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    // (derived, original) ranges, contiguous and increasing in both
+    pieces: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl OffsetMap {
+    /// The map of text that is its original unchanged.
+    pub fn identity(len: usize) -> Self {
+        let mut map = Self::default();
+        map.push(0..len, 0..len);
+        map
+    }
+
+    /// Appends a piece of derived text made from `original`, merging it with
+    /// the previous piece when both are unchanged copies that run on.
+    pub fn push(&mut self, derived: Range<usize>, original: Range<usize>) {
+        if let Some((last_derived, last_original)) = self.pieces.last_mut() {
+            let runs_on = last_derived.end == derived.start && last_original.end == original.start;
+            let unchanged = |d: &Range<usize>, o: &Range<usize>| d.len() == o.len();
+            if runs_on && unchanged(last_derived, last_original) && unchanged(&derived, &original) {
+                last_derived.end = derived.end;
+                last_original.end = original.end;
+                return;
+            }
+        }
+        if !derived.is_empty() {
+            self.pieces.push((derived, original));
+        }
+    }
+
+    /// The range of the original a derived byte range came from, widened to
+    /// whole pieces where it starts or ends inside a changed one.
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.pieces.partition_point(|(derived, _)| derived.end <= range.start);
+        let start = match self.pieces.get(start) {
+            Some((derived, original)) if derived.len() == original.len() && derived.start <= range.start => {
+                original.start + (range.start - derived.start)
+            }
+            Some((_, original)) => original.start,
+            None => self.pieces.last().map_or(range.start, |(_, original)| original.end),
+        };
+
+        let end = self.pieces.partition_point(|(derived, _)| derived.end < range.end);
+        let end = match self.pieces.get(end) {
+            Some((derived, original)) if derived.len() == original.len() && derived.start <= range.end => {
+                original.start + (range.end - derived.start)
+            }
+            Some((_, original)) => original.end,
+            None => self.pieces.last().map_or(range.end, |(_, original)| original.end),
+        };
+        start..end.max(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_offsets_and_contractions() {
+        let text = "Don't go—it's 'great', café!";
+        let tokens = tokenize(text);
+        let words: Vec<&str> = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(words, ["Don't", "go", "it's", "great", "café"]);
+
+        for token in &tokens {
+            assert_eq!(&text[token.start..token.end], token.text);
+        }
+    }
+
+    #[test]
+    fn test_sentences_offsets_and_abbreviations() {
+        let text = "Dr. Smith called, e.g. about the bill. It was \"fine!\" Really?! no\nNext line…  Done";
+        let found = sentences(text);
+        let texts: Vec<&str> = found.iter().map(|s| s.text).collect();
+        assert_eq!(
+            texts,
+            [
+                "Dr. Smith called, e.g. about the bill.",
+                "It was \"fine!\"",
+                "Really?! no",
+                "Next line…",
+                "Done"
+            ]
+        );
+
+        for sentence in &found {
+            assert_eq!(&text[sentence.start..sentence.end], sentence.text);
+        }
+    }
+
+    #[test]
+    fn test_offset_map() {
+        // "ab[X]cd" from "ab<secret>cd", with "<secret>" at 2..10
+        let mut map = OffsetMap::default();
+        map.push(0..1, 0..1);
+        map.push(1..2, 1..2);
+        map.push(2..5, 2..10);
+        map.push(5..7, 10..12);
+        assert_eq!(map.pieces.len(), 3);
+
+        assert_eq!(map.original_range(0..2), 0..2);
+        assert_eq!(map.original_range(5..7), 10..12);
+        assert_eq!(map.original_range(1..6), 1..11);
+        // Inside the placeholder, the whole secret
+        assert_eq!(map.original_range(3..4), 2..10);
+        assert_eq!(map.original_range(7..7), 12..12);
+        assert_eq!(OffsetMap::identity(4).original_range(1..3), 1..3);
+    }
+}
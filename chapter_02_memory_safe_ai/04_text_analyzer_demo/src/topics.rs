@@ -0,0 +1,441 @@
+//! Offline keyphrase extraction in the style of RAKE.
+//!
+//! Candidate phrases are runs of content words between stopwords and
+//! punctuation. Each word scores `degree / frequency` over all candidates and
+//! a phrase scores the sum of its words, so words that keep company in longer
+//! phrases rank higher. Topics get a category from a term taxonomy.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+
+use crate::analysis::{Mention, SentimentScore, Topic};
+use crate::language::Language;
+use crate::redact;
+use crate::text::{sentences, tokenize, Sentence};
+
+const ENGLISH_TAXONOMY: &str = include_str!("../data/taxonomy_en.tsv");
+
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "also", "am", "an", "and", "any",
+    "are", "as", "at", "be", "because", "been", "before", "being", "below", "between", "both",
+    "but", "by", "can", "could", "did", "do", "does", "doing", "down", "during", "each", "even",
+    "ever", "every", "few", "for", "from", "further", "get", "got", "had", "has", "have",
+    "having", "he", "her", "here", "hers", "him", "his", "how", "i", "i'm", "i've", "if", "in",
+    "into", "is", "it", "it's", "its", "just", "me", "more", "most", "much", "my", "myself",
+    "no", "nor", "not", "now", "of", "off", "on", "once", "one", "only", "or", "other", "our",
+    "ours", "out", "over", "own", "quite", "rather", "really", "same", "she", "should", "so",
+    "some", "still", "such", "than", "that", "that's", "the", "their", "them", "then", "there",
+    "these", "they", "this", "those", "through", "to", "too", "under", "until", "up", "us",
+    "very", "want", "was", "we", "were", "what", "when", "where", "which", "while", "who",
+    "whom", "why", "will", "with", "would", "yet", "you", "your", "yours",
+];
+
+const SPANISH_STOPWORDS: &[&str] = &[
+    "a", "al", "algo", "algunos", "ante", "aquí", "así", "bastante", "cada", "como", "con",
+    "cuando", "de", "del", "desde", "donde", "durante", "e", "el", "ella", "ellos", "en", "entre",
+    "era", "es", "esa", "ese", "eso", "esta", "está", "estaba", "este", "esto", "fue", "ha",
+    "han", "hasta", "hay", "la", "las", "le", "les", "lo", "los", "me", "mi", "mis", "mucho",
+    "muy", "más", "nada", "ni", "no", "nos", "o", "para", "pero", "poco", "por", "porque", "que",
+    "qué", "se", "ser", "si", "sin", "sobre", "son", "su", "sus", "también", "tan", "te", "tiene",
+    "todo", "todos", "tu", "un", "una", "uno", "unos", "y", "ya", "yo",
+];
+
+const GERMAN_STOPWORDS: &[&str] = &[
+    "aber", "alle", "als", "also", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "da",
+    "damit", "das", "dass", "dem", "den", "der", "des", "die", "dies", "diese", "dieser", "doch",
+    "du", "durch", "ein", "eine", "einem", "einen", "einer", "er", "es", "etwas", "für", "gar",
+    "hat", "hatte", "ich", "ihr", "im", "in", "ist", "ja", "kein", "keine", "man", "mein", "mir",
+    "mit", "nach", "nicht", "noch", "nur", "oder", "schon", "sehr", "sich", "sie", "sind", "so",
+    "um", "und", "uns", "vom", "von", "vor", "war", "waren", "was", "weil", "wenn", "wie", "wir",
+    "wird", "wirklich", "zu", "zum", "zur",
+];
+
+/// Words that start a new clause, so a topic's sentiment is read from its own
+/// side of "the screen is great but the battery is awful".
+const ENGLISH_CLAUSE_BREAKS: &[&str] = &["but", "however", "although", "though", "yet"];
+const SPANISH_CLAUSE_BREAKS: &[&str] = &["pero", "sino", "aunque"];
+const GERMAN_CLAUSE_BREAKS: &[&str] = &["aber", "sondern", "doch", "obwohl"];
+
+/// Characters that end a candidate phrase in addition to stopwords.
+const PHRASE_DELIMITERS: &[char] = &[
+    '.', ',', ';', ':', '!', '?', '(', ')', '[', ']', '{', '}', '"', '\n', '\t', '/', '|',
+];
+
+/// Maps terms (one or more words) to a topic category.
+#[derive(Debug, Clone)]
+pub struct Taxonomy {
+    terms: HashMap<String, String>,
+    longest_term: usize,
+    default_category: String,
+}
+
+impl Taxonomy {
+    /// The bundled English product-feedback taxonomy.
+    pub fn english() -> Self {
+        Self::parse(ENGLISH_TAXONOMY).expect("bundled taxonomy is valid")
+    }
+
+    /// Loads a taxonomy file of `term<TAB>category` lines.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses taxonomy lines; blank lines and `#` comments are skipped.
+    pub fn parse(source: &str) -> io::Result<Self> {
+        let mut terms = HashMap::new();
+        for (number, line) in source.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (term, category) = line
+                .split_once('\t')
+                .map(|(term, category)| (normalize_term(term), category.trim()))
+                .filter(|(term, category)| !term.is_empty() && !category.is_empty())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("taxonomy line {}: expected `term<TAB>category`", number + 1),
+                    )
+                })?;
+            terms.insert(term, category.to_string());
+        }
+
+        let longest_term = terms.keys().map(|t| t.split(' ').count()).max().unwrap_or(0);
+        Ok(Self { terms, longest_term, default_category: "general".to_string() })
+    }
+
+    /// Category for topics that match no term.
+    pub fn with_default_category(mut self, category: impl Into<String>) -> Self {
+        self.default_category = category.into();
+        self
+    }
+
+    /// Category of the longest term found in `words`, trying singular forms too.
+    pub fn categorize(&self, words: &[String]) -> &str {
+        for size in (1..=self.longest_term.min(words.len())).rev() {
+            for window in words.windows(size) {
+                let term = window.join(" ");
+                let singular = window.iter().map(|w| singular(w)).collect::<Vec<_>>().join(" ");
+                if let Some(category) = self.terms.get(&term).or_else(|| self.terms.get(&singular)) {
+                    return category;
+                }
+            }
+        }
+        &self.default_category
+    }
+}
+
+impl Default for Taxonomy {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+// Terms are hashed in sorted order so equal taxonomies hash equally
+impl Hash for Taxonomy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_unstable();
+        terms.hash(state);
+        self.default_category.hash(state);
+    }
+}
+
+/// Ranks keyphrases in a text and turns the best into `Topic`s.
+#[derive(Debug, Clone)]
+pub struct TopicExtractor {
+    stopwords: HashSet<String>,
+    clause_breaks: HashSet<String>,
+    taxonomy: Taxonomy,
+    max_topics: usize,
+    max_phrase_words: usize,
+}
+
+impl TopicExtractor {
+    pub fn new(taxonomy: Taxonomy) -> Self {
+        Self::for_language(Language::English, taxonomy)
+    }
+
+    /// Splits phrases on `language`'s stopwords, or English ones for
+    /// languages without a bundled list.
+    pub fn for_language(language: Language, taxonomy: Taxonomy) -> Self {
+        let (stopwords, clause_breaks) = match language {
+            Language::Spanish => (SPANISH_STOPWORDS, SPANISH_CLAUSE_BREAKS),
+            Language::German => (GERMAN_STOPWORDS, GERMAN_CLAUSE_BREAKS),
+            Language::English | Language::French | Language::Portuguese => {
+                (ENGLISH_STOPWORDS, ENGLISH_CLAUSE_BREAKS)
+            }
+        };
+        Self {
+            stopwords: stopwords.iter().map(|w| w.to_string()).collect(),
+            clause_breaks: clause_breaks.iter().map(|w| w.to_string()).collect(),
+            taxonomy,
+            max_topics: 5,
+            max_phrase_words: 3,
+        }
+    }
+
+    /// Treats `words` as phrase boundaries too, e.g. sentiment words that
+    /// describe a topic rather than name it.
+    pub fn exclude_words<I: IntoIterator<Item = String>>(mut self, words: I) -> Self {
+        self.stopwords.extend(words.into_iter().map(|w| w.to_lowercase()));
+        self
+    }
+
+    pub fn with_max_topics(mut self, max_topics: usize) -> Self {
+        self.max_topics = max_topics;
+        self
+    }
+
+    /// The best-ranked topics with the byte offsets of their mentions in
+    /// `text`; their `sentiment` is left unset.
+    pub fn extract(&self, text: &str) -> Vec<Topic> {
+        let phrases = self.candidate_phrases(text);
+
+        let mut frequency: HashMap<&str, f64> = HashMap::new();
+        let mut degree: HashMap<&str, f64> = HashMap::new();
+        for phrase in &phrases {
+            for word in &phrase.words {
+                *frequency.entry(word).or_default() += 1.0;
+                *degree.entry(word).or_default() += phrase.words.len() as f64;
+            }
+        }
+
+        // Unique phrases in order of first appearance, with their RAKE score
+        let mut scored: Vec<(&Vec<String>, f64)> = Vec::new();
+        for phrase in phrases.iter().map(|p| &p.words) {
+            if scored.iter().any(|(seen, _)| *seen == phrase) {
+                continue;
+            }
+            let score = phrase.iter().map(|w| degree[w.as_str()] / frequency[w.as_str()]).sum();
+            scored.push((phrase, score));
+        }
+
+        let total: f64 = scored.iter().map(|(_, score)| score).sum();
+        // Stable sort keeps earlier phrases first on ties
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        scored
+            .into_iter()
+            .take(self.max_topics)
+            .map(|(words, score)| Topic {
+                name: words.join(" "),
+                relevance: score / total,
+                category: self.taxonomy.categorize(words).to_string(),
+                sentiment: None,
+                mentions: phrases
+                    .iter()
+                    .filter(|phrase| phrase.words == *words)
+                    .map(|phrase| Mention { start: phrase.start, end: phrase.end })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Like `extract`, also giving each topic the mean of what `score` makes
+    /// of the clause around each of its mentions: the mention's sentence, cut
+    /// at clause-break words such as "but" on either side.
+    pub fn extract_with_sentiment(&self, text: &str, score: impl Fn(&str) -> SentimentScore) -> Vec<Topic> {
+        let sentences = sentences(text);
+        let mut topics = self.extract(text);
+        for topic in &mut topics {
+            let scores: Vec<SentimentScore> =
+                topic.mentions.iter().map(|mention| score(self.clause_around(text, &sentences, mention))).collect();
+            topic.sentiment = mean_sentiment(&scores);
+        }
+        topics
+    }
+
+    fn clause_around<'a>(&self, text: &'a str, sentences: &[Sentence], mention: &Mention) -> &'a str {
+        let (sentence_start, sentence_end) = sentences
+            .iter()
+            .find(|s| s.start <= mention.start && mention.end <= s.end)
+            .map_or((0, text.len()), |s| (s.start, s.end));
+
+        let (mut start, mut end) = (sentence_start, sentence_end);
+        for token in tokenize(&text[sentence_start..sentence_end]) {
+            if !self.clause_breaks.contains(&token.lowercase()) {
+                continue;
+            }
+            let (break_start, break_end) = (sentence_start + token.start, sentence_start + token.end);
+            if break_end <= mention.start {
+                start = break_end;
+            } else if break_start >= mention.end {
+                end = break_start;
+                break;
+            }
+        }
+        &text[start..end]
+    }
+
+    fn candidate_phrases(&self, text: &str) -> Vec<Phrase> {
+        let mut phrases = Vec::new();
+        let mut current: Option<Phrase> = None;
+        let mut previous_end = 0;
+        for token in tokenize(text) {
+            if text[previous_end..token.start].contains(PHRASE_DELIMITERS) {
+                phrases.extend(current.take());
+            }
+            previous_end = token.end;
+
+            let word = token.lowercase();
+            // Redaction placeholders such as `[EMAIL]` are not topics
+            let bracketed = token.start.checked_sub(1).and_then(|start| text.get(start..token.end + 1));
+            let is_content = word.chars().count() > 1
+                && !bracketed.is_some_and(redact::is_placeholder)
+                && !word.chars().all(|c| c.is_numeric())
+                && !self.stopwords.contains(&word);
+            if !is_content {
+                phrases.extend(current.take());
+                continue;
+            }
+
+            let phrase = current.get_or_insert(Phrase { words: Vec::new(), start: token.start, end: token.end });
+            phrase.words.push(word);
+            phrase.end = token.end;
+            if phrase.words.len() == self.max_phrase_words {
+                phrases.extend(current.take());
+            }
+        }
+        phrases.extend(current);
+        phrases
+    }
+}
+
+/// A candidate keyphrase and where it occurs.
+struct Phrase {
+    words: Vec<String>,
+    start: usize,
+    end: usize,
+}
+
+/// Every case-insensitive occurrence of `name` in `text`, matched word by word.
+pub fn find_mentions(text: &str, name: &str) -> Vec<Mention> {
+    let words: Vec<String> = tokenize(name).iter().map(|t| t.lowercase()).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let tokens = tokenize(text);
+    tokens
+        .windows(words.len())
+        .filter(|window| window.iter().zip(&words).all(|(token, word)| token.lowercase() == *word))
+        .map(|window| Mention { start: window[0].start, end: window[words.len() - 1].end })
+        .collect()
+}
+
+fn mean_sentiment(scores: &[SentimentScore]) -> Option<SentimentScore> {
+    if scores.is_empty() {
+        return None;
+    }
+    let n = scores.len() as f64;
+    Some(SentimentScore {
+        positive: scores.iter().map(|s| s.positive).sum::<f64>() / n,
+        negative: scores.iter().map(|s| s.negative).sum::<f64>() / n,
+        neutral: scores.iter().map(|s| s.neutral).sum::<f64>() / n,
+    })
+}
+
+impl Default for TopicExtractor {
+    fn default() -> Self {
+        Self::new(Taxonomy::english())
+    }
+}
+
+impl Hash for TopicExtractor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut stopwords: Vec<_> = self.stopwords.iter().collect();
+        stopwords.sort_unstable();
+        stopwords.hash(state);
+        let mut clause_breaks: Vec<_> = self.clause_breaks.iter().collect();
+        clause_breaks.sort_unstable();
+        clause_breaks.hash(state);
+        self.taxonomy.hash(state);
+        self.max_topics.hash(state);
+        self.max_phrase_words.hash(state);
+    }
+}
+
+fn normalize_term(term: &str) -> String {
+    tokenize(term).iter().map(|t| t.lowercase()).collect::<Vec<_>>().join(" ")
+}
+
+// Crude plural stripping, enough to match "prices" against "price"
+fn singular(word: &str) -> &str {
+    match word.strip_suffix('s') {
+        Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem,
+        _ => word,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranks_longer_phrases_higher() {
+        let topics = TopicExtractor::default()
+            .extract("The customer service team was slow. Shipping was fast.");
+        let names: Vec<&str> = topics.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["customer service team", "slow", "shipping", "fast"]);
+        assert_eq!(topics[0].category, "support");
+
+        let total: f64 = topics.iter().map(|t| t.relevance).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(topics[0].relevance > topics[1].relevance);
+    }
+
+    #[test]
+    fn test_categories_from_taxonomy() {
+        let extractor = TopicExtractor::default().exclude_words(["terrible".to_string()]);
+        let topics = extractor.extract("Terrible prices, and the delivery driver was late");
+
+        assert_eq!(topics[0].name, "delivery driver");
+        assert_eq!(topics[0].category, "delivery");
+        assert_eq!(topics[1].name, "prices");
+        assert_eq!(topics[1].category, "pricing");
+        assert_eq!(topics[2].name, "late");
+        assert_eq!(topics[2].category, "general");
+    }
+
+    #[test]
+    fn test_custom_taxonomy() {
+        let taxonomy = Taxonomy::parse("# comment\nlanding page\tmarketing\n")
+            .unwrap()
+            .with_default_category("other");
+        let topics = TopicExtractor::new(taxonomy).extract("New landing page copy");
+        assert_eq!(topics[0].category, "marketing");
+
+        assert!(Taxonomy::parse("no tab here\n").is_err());
+    }
+
+    #[test]
+    fn test_mentions_and_clause_sentiment() {
+        let text = "The battery is great but the screen is dim. Battery life matters.";
+        let score = |clause: &str| {
+            let positive = if clause.contains("great") { 1.0 } else { 0.0 };
+            SentimentScore { positive, negative: 0.0, neutral: 1.0 - positive }
+        };
+        let topics = TopicExtractor::default().extract_with_sentiment(text, score);
+
+        let screen = topics.iter().find(|t| t.name == "screen").unwrap();
+        assert_eq!(screen.mentions, [Mention { start: 29, end: 35 }]);
+        assert_eq!(screen.sentiment.as_ref().unwrap().positive, 0.0);
+
+        let battery = topics.iter().find(|t| t.name == "battery").unwrap();
+        assert_eq!(&text[battery.mentions[0].start..battery.mentions[0].end], "battery");
+        assert_eq!(battery.sentiment.as_ref().unwrap().positive, 1.0);
+
+        let found = find_mentions(text, "Battery");
+        assert_eq!(found.len(), 2);
+        assert_eq!(&text[found[1].start..found[1].end], "Battery");
+    }
+
+    #[test]
+    fn test_no_topics_in_stopwords() {
+        assert!(TopicExtractor::default().extract("It is what it is.").is_empty());
+    }
+}